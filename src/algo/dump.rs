@@ -0,0 +1,321 @@
+use std::io::Write;
+use std::path::PathBuf;
+use crate::core::args::DumpArgs;
+use crate::core::output;
+use crate::parser::{kind_name, qualified_name, signature, Attribute, VisitFlags, Visitor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct Position
+{
+  pub line: u32,
+  pub column: u32
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Range
+{
+  pub file: Option<PathBuf>,
+  pub start: Position,
+  pub end: Position
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct Node
+{
+  pub kind: String,
+  #[serde(skip_serializing_if = "Option::is_none")] pub name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub qualified_name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub signature: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub usr: Option<String>,
+  #[serde(rename = "type", skip_serializing_if = "Option::is_none")] pub type_name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub canonical_type: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub access: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub storage_class: Option<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")] pub attributes: Vec<Attribute>,
+  #[serde(skip_serializing_if = "Option::is_none")] pub range: Option<Range>,
+  #[serde(skip_serializing_if = "Vec::is_empty")] pub children: Vec<Node>,
+  #[serde(skip)] matched: bool,
+  #[serde(skip)] skipped: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax
+{
+  Json,
+  Sexp
+}
+
+// dumps every translation unit as a tree of entities. entities not matching file or line filters are pruned,
+// entities not matching name filter are kept only as parents of matching ones.
+// trees go to output file or stdout, or are emitted as records in structured formats unless output file is given.
+// json array stays open while watching, trees of every round are appended to it
+pub struct TreeDump
+{
+  syntax: Syntax,
+  file: Option<String>,
+  lines: Option<(u32, u32)>,
+  name: Option<regex::Regex>,
+  max_depth: Option<usize>,
+  watch: bool,
+  out: Option<Box<dyn Write>>,
+  opened: bool,
+  stack: Vec<Node>,
+  root: Option<Node>,
+  count: usize
+}
+
+impl TreeDump
+{
+  pub fn new(args: &DumpArgs) -> anyhow::Result<Self>
+  {
+    Ok(TreeDump {
+      syntax: match args.syntax.as_str() {
+        "json" => Syntax::Json,
+        "sexp" => Syntax::Sexp,
+        _ => return Err(anyhow::anyhow!("unknown tree syntax: {}", args.syntax))
+      },
+      file: args.file.clone(),
+      lines: args.lines.as_deref().map(parse_lines).transpose()?,
+      name: args.name.as_deref().map(regex::Regex::new).transpose()?,
+      max_depth: args.max_depth,
+      watch: args.process.watch,
+      out: match &args.output {
+        Some(path) => Some(Box::new(std::io::BufWriter::new(std::fs::File::create(path)?))),
+        None if output::is_text() => {
+          output::reserve_stdout();
+          Some(Box::new(std::io::stdout()))
+        },
+        None => None
+      },
+      opened: false,
+      stack: vec![],
+      root: None,
+      count: 0
+    })
+  }
+
+  fn accepts_range(&self, range: &Option<Range>) -> bool
+  {
+    let Some(range) = range else { return self.file.is_none() && self.lines.is_none() };
+    let in_file = match (&self.file, &range.file) {
+      (Some(file), Some(path)) => path.to_string_lossy().replace('\\', "/").ends_with(&file.replace('\\', "/")),
+      (Some(_), None) => false,
+      (None, _) => true
+    };
+    let in_lines = match self.lines {
+      Some((first, last)) => range.start.line <= last && range.end.line >= first,
+      None => true
+    };
+    in_file && in_lines
+  }
+}
+
+impl Visitor for TreeDump
+{
+  fn begin_run(&mut self, _opts: &crate::parser::CompileOptions) -> anyhow::Result<()>
+  {
+    let Some(out) = &mut self.out else { return Ok(()) };
+    if self.syntax == Syntax::Json && !self.opened {
+      writeln!(out, "[")?;
+      self.opened = true;
+    }
+    Ok(())
+  }
+
+  fn end_run(&mut self) -> anyhow::Result<()>
+  {
+    let Some(out) = &mut self.out else { return Ok(()) };
+    if self.syntax == Syntax::Json && !self.watch {
+      writeln!(out, "\n]")?;
+    }
+    out.flush()?;
+    Ok(())
+  }
+
+  fn end_translation_unit(&mut self, _opt: &crate::parser::CompileOption) -> anyhow::Result<()>
+  {
+    let Some(root) = self.root.take() else { return Ok(()) };
+    let Some(out) = &mut self.out else {
+      output::emit("tree", root);
+      return Ok(());
+    };
+    match self.syntax {
+      Syntax::Json => {
+        if self.count > 0 {
+          writeln!(out, ",")?;
+        }
+        write!(out, "{}", serde_json::to_string_pretty(&root)?)?;
+      },
+      Syntax::Sexp => writeln!(out, "{}", root.to_sexp())?
+    }
+    self.count += 1;
+    Ok(())
+  }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    let depth = self.stack.len();
+    let mut node = Node::from_entity(entity);
+    if depth > 0 && !self.accepts_range(&node.range) {
+      node.skipped = true;
+      self.stack.push(node);
+      return VisitFlags::PRUNE;
+    }
+    node.matched = match &self.name {
+      Some(re) => [&node.name, &node.qualified_name].iter().any(|name| name.as_ref().is_some_and(|name| re.is_match(name))),
+      None => true
+    };
+    self.stack.push(node);
+    match self.max_depth {
+      Some(max_depth) if depth >= max_depth => VisitFlags::PRUNE,
+      _ => VisitFlags::empty()
+    }
+  }
+
+  fn leave_entity(&mut self, _entity: &clang::Entity)
+  {
+    let Some(node) = self.stack.pop() else { return };
+    match self.stack.last_mut() {
+      Some(parent) => {
+        if !node.skipped && (node.matched || !node.children.is_empty()) {
+          parent.children.push(node);
+        }
+      },
+      None => self.root = Some(node)
+    }
+  }
+}
+
+impl Node
+{
+  pub fn from_entity(entity: &clang::Entity) -> Self
+  {
+    let range = entity.get_range().map(|range| {
+      let (start, end) = (range.get_start().get_file_location(), range.get_end().get_file_location());
+      Range {
+        file: start.file.map(|file| file.get_path()),
+        start: Position { line: start.line, column: start.column },
+        end: Position { line: end.line, column: end.column }
+      }
+    });
+    let ty = entity.get_type();
+    Node {
+      kind: kind_name(entity),
+      name: entity.get_name(),
+      qualified_name: entity.get_name().map(|_| qualified_name(entity)),
+      signature: signature(entity),
+      usr: entity.get_usr().map(|usr| usr.0),
+      type_name: ty.map(|ty| ty.get_display_name()),
+      canonical_type: ty.map(|ty| ty.get_canonical_type().get_display_name()),
+      access: entity.get_accessibility().map(|access| format!("{:?}", access).to_lowercase()),
+      storage_class: entity.get_storage_class().map(|storage| format!("{:?}", storage).to_lowercase()),
+      attributes: entity
+        .get_children()
+        .iter()
+        .filter(|c| c.is_attribute())
+        .map(|c| Attribute { kind: kind_name(c), spelling: c.get_display_name() })
+        .collect(),
+      range,
+      children: vec![],
+      matched: true,
+      skipped: false
+    }
+  }
+
+  // `(Kind :key "value" ... children)`, one node per line indented by depth
+  pub fn to_sexp(&self) -> String
+  {
+    let mut out = String::new();
+    self.write_sexp(0, &mut out);
+    out
+  }
+
+  fn write_sexp(&self, depth: usize, out: &mut String)
+  {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    out.push_str(&format!("{}({}", "  ".repeat(depth), self.kind));
+    let fields = [
+      ("name", &self.name),
+      ("qualified-name", &self.qualified_name),
+      ("signature", &self.signature),
+      ("usr", &self.usr),
+      ("type", &self.type_name),
+      ("canonical-type", &self.canonical_type),
+      ("access", &self.access),
+      ("storage-class", &self.storage_class)
+    ];
+    for (key, value) in fields {
+      if let Some(value) = value {
+        out.push_str(&format!(" :{} {}", key, quote(value)));
+      }
+    }
+    if !self.attributes.is_empty() {
+      let attributes = self.attributes
+        .iter()
+        .map(|attr| quote(attr.spelling.as_deref().unwrap_or(&attr.kind)))
+        .collect::<Vec<_>>();
+      out.push_str(&format!(" :attributes ({})", attributes.join(" ")));
+    }
+    if let Some(range) = &self.range {
+      out.push_str(&format!(" :range ({} {} {} {} {})",
+        quote(&range.file.as_ref().map(|f| f.display().to_string()).unwrap_or_default()),
+        range.start.line, range.start.column, range.end.line, range.end.column
+      ));
+    }
+    for child in &self.children {
+      out.push('\n');
+      child.write_sexp(depth + 1, out);
+    }
+    out.push(')');
+  }
+}
+
+// `10-20` or a single line `15`
+fn parse_lines(lines: &str) -> anyhow::Result<(u32, u32)>
+{
+  let parse = |s: &str| s.trim().parse::<u32>().map_err(|_| anyhow::anyhow!("invalid line range: {}", lines));
+  match lines.split_once('-') {
+    Some((first, last)) => Ok((parse(first)?, parse(last)?)),
+    None => parse(lines).map(|line| (line, line))
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_sexp()
+  {
+    let node = Node {
+      kind: "Namespace".to_string(),
+      name: Some("floppy".to_string()),
+      qualified_name: Some("floppy".to_string()),
+      range: Some(Range { file: Some(PathBuf::from("a.h")), start: Position { line: 1, column: 1 }, end: Position { line: 5, column: 2 } }),
+      children: vec![Node {
+        kind: "FunctionDecl".to_string(),
+        name: Some("foo".to_string()),
+        signature: Some("void floppy::foo(int) noexcept".to_string()),
+        type_name: Some("const char *(\"x\")".to_string()),
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+    assert_eq!(node.to_sexp(), concat!(
+      "(Namespace :name \"floppy\" :qualified-name \"floppy\" :range (\"a.h\" 1 1 5 2)\n",
+      "  (FunctionDecl :name \"foo\" :signature \"void floppy::foo(int) noexcept\" :type \"const char *(\\\"x\\\")\"))"
+    ));
+    let json = serde_json::to_value(&node).unwrap();
+    assert_eq!(json["children"][0]["type"], "const char *(\"x\")");
+    assert!(json["children"][0].get("children").is_none());
+  }
+
+  #[test]
+  fn test_parse_lines()
+  {
+    assert_eq!(parse_lines("10-20").unwrap(), (10, 20));
+    assert_eq!(parse_lines("15").unwrap(), (15, 15));
+    assert!(parse_lines("a-b").is_err());
+  }
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+use colored::Colorize;
+use crate::core::args::InfoArgs;
+use crate::core::output;
+use crate::parser::{CompileOption, Parser};
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct InfoRecord<'a>
+{
+  #[serde(flatten)] option: &'a CompileOption,
+  arguments: Vec<String>
+}
+
+// prints a summary of compile database, or entries selected by --file together with exact arguments passed to libclang for them.
+// structured formats always get entries. in verbose mode the summary is already printed by parser
+pub fn print(parser: &Parser, args: &InfoArgs, verbose: bool) -> anyhow::Result<()>
+{
+  let options = parser.options();
+  let selected = options.options
+    .iter()
+    .filter(|opt| args.file.as_ref().is_none_or(|file| ends_with(&opt.source, file)))
+    .collect::<Vec<_>>();
+  if let Some(file) = &args.file {
+    anyhow::ensure!(!selected.is_empty(), "no compile database entry for {}", file);
+  }
+  if output::is_text() && args.file.is_none() {
+    if !verbose {
+      options.pretty_print();
+    }
+    return Ok(());
+  }
+  for opt in selected {
+    let arguments = parser.compiler_flags(opt, &args.process);
+    if !output::is_text() {
+      output::emit("compile_option", InfoRecord { option: opt, arguments });
+      continue;
+    }
+    println!();
+    opt.pretty_print();
+    println!("\targuments: [");
+    for argument in &arguments {
+      println!("\t\t{}", argument.bold().white());
+    }
+    println!("\t]");
+  }
+  Ok(())
+}
+
+// whether last components of path are the given ones, regardless of separators
+fn ends_with(path: &Path, suffix: &str) -> bool
+{
+  let components = |path: &str| path
+    .split(['/', '\\'])
+    .filter(|component| !component.is_empty() && *component != ".")
+    .map(str::to_string)
+    .collect::<Vec<_>>();
+  let suffix = components(suffix);
+  !suffix.is_empty() && components(&path.to_string_lossy()).ends_with(&suffix)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_ends_with()
+  {
+    assert!(ends_with(Path::new(r"D:\dev\my\floppy\src\detail\rtti.cc"), "detail/rtti.cc"));
+    assert!(ends_with(Path::new("/home/floppy/src/main.cc"), "src\\main.cc"));
+    assert!(!ends_with(Path::new("/home/floppy/src/main.cc"), "test/main.cc"));
+    assert!(!ends_with(Path::new("/home/floppy/src/domain.cc"), "main.cc"));
+    assert!(ends_with(Path::new("/home/floppy/src/main.cc"), "./main.cc"));
+  }
+}
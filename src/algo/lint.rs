@@ -0,0 +1,88 @@
+use colored::Colorize;
+use crate::core::output;
+use crate::parser::{qualified_name, Location, VisitFlags, Visitor};
+use crate::pb_print;
+
+// functions with more parameters than this are reported
+const MAX_PARAMETERS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Warning
+{
+  pub check: &'static str,
+  pub message: String,
+  pub location: Location
+}
+
+// reports common design problems in declarations: polymorphic classes without virtual destructors,
+// implicit converting constructors and functions with too many parameters
+#[derive(Debug, Default)]
+pub struct Lint
+{
+  warnings: Vec<Warning>
+}
+
+impl Lint
+{
+  fn warn(&mut self, check: &'static str, entity: &clang::Entity, message: String)
+  {
+    self.warnings.push(Warning { check, message, location: Location::from_entity(entity) });
+  }
+}
+
+impl Visitor for Lint
+{
+  fn end_run(&mut self) -> anyhow::Result<()>
+  {
+    for warning in &self.warnings {
+      if !output::is_text() {
+        output::emit("diagnostic", serde_json::json!({
+          "severity": "warning",
+          "check": warning.check,
+          "message": warning.message,
+          "location": warning.location
+        }));
+        continue;
+      }
+      pb_print!("{}:{}:{}: {} {} [{}]",
+        warning.location.file.as_ref().map(|f| f.display().to_string()).unwrap_or("unknown".to_string()).bold().magenta(),
+        warning.location.line,
+        warning.location.column,
+        "warning:".bold().yellow(),
+        warning.message,
+        warning.check.dimmed()
+      );
+    }
+    pb_print!("☑️ {} warnings", self.warnings.len().to_string().bold().green());
+    self.warnings.clear();
+    Ok(())
+  }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    use clang::EntityKind as K;
+    match entity.get_kind() {
+      K::ClassDecl | K::StructDecl | K::ClassTemplate if entity.is_definition() => {
+        let children = entity.get_children();
+        let is_polymorphic = children.iter().any(|c| c.get_kind() == K::Method && c.is_virtual_method());
+        let has_bases = children.iter().any(|c| c.get_kind() == K::BaseSpecifier);
+        let has_virtual_destructor = children.iter().any(|c| c.get_kind() == K::Destructor && c.is_virtual_method());
+        if is_polymorphic && !has_bases && !has_virtual_destructor {
+          self.warn("virtual-destructor", entity, format!("{} has virtual methods but no virtual destructor", qualified_name(entity)));
+        }
+      },
+      K::Constructor if entity.is_converting_constructor() && !entity.is_copy_constructor() && !entity.is_move_constructor() => {
+        self.warn("implicit-conversion", entity, format!("constructor of {} allows implicit conversions, consider marking it explicit",
+          entity.get_semantic_parent().map(|parent| qualified_name(&parent)).unwrap_or_default()
+        ));
+      },
+      _ => {}
+    }
+    if let Some(arguments) = entity.get_arguments().filter(|arguments| arguments.len() > MAX_PARAMETERS) {
+      self.warn("too-many-parameters", entity, format!("{} takes {} parameters, more than {}",
+        qualified_name(entity), arguments.len(), MAX_PARAMETERS
+      ));
+    }
+    VisitFlags::SKIP_BODY
+  }
+}
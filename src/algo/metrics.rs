@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use colored::Colorize;
+use crate::core::output;
+use crate::parser::{Decl, DeclKind, VisitFlags, Visitor};
+use crate::pb_print;
+
+// counts declarations by kind and measures function sizes and nesting depth.
+// sizes, depth and entities other than declarations are not cached, so cached declarations are not accepted
+// and every translation unit is walked, which keeps totals the same with and without --cache-dir
+#[derive(Debug, Default)]
+pub struct Metrics
+{
+  translation_units: usize,
+  entities: usize,
+  counts: BTreeMap<&'static str, usize>,
+  parameters: usize,
+  function_lines: usize,
+  longest: Option<(String, u32)>,
+  depth: usize,
+  max_depth: usize
+}
+
+impl Metrics
+{
+  fn count(&mut self, group: &'static str)
+  {
+    *self.counts.entry(group).or_default() += 1;
+  }
+
+  fn function(&mut self, name: String, parameters: usize, lines: Option<u32>)
+  {
+    self.count("functions");
+    self.parameters += parameters;
+    if let Some(lines) = lines {
+      self.function_lines += lines as usize;
+      if self.longest.as_ref().is_none_or(|(_, longest)| lines > *longest) {
+        self.longest = Some((name, lines));
+      }
+    }
+  }
+
+  fn group(decl_kind: &DeclKind) -> &'static str
+  {
+    match decl_kind {
+      DeclKind::Namespace => "namespaces",
+      DeclKind::Class { .. } => "classes",
+      DeclKind::Function { .. } => "functions",
+      DeclKind::Enum { .. } => "enums",
+      DeclKind::Enumerator { .. } => "enumerators",
+      DeclKind::Typedef { .. } => "typedefs",
+      DeclKind::Variable { .. } => "variables"
+    }
+  }
+}
+
+// variables and parameters declared inside a function body, which are not counted as variables
+fn is_local(entity: &clang::Entity) -> bool
+{
+  use clang::EntityKind as K;
+  matches!(entity.get_kind(), K::VarDecl | K::ParmDecl) && entity.get_semantic_parent().is_some_and(|parent| matches!(parent.get_kind(),
+    K::FunctionDecl | K::Method | K::Constructor | K::Destructor | K::ConversionFunction | K::FunctionTemplate
+  ))
+}
+
+impl Visitor for Metrics
+{
+  fn end_run(&mut self) -> anyhow::Result<()>
+  {
+    let functions = self.counts.get("functions").copied().unwrap_or_default();
+    let average = |total: usize| match functions {
+      0 => 0.0,
+      n => total as f64 / n as f64
+    };
+    output::emit("metrics", serde_json::json!({
+      "translation_units": self.translation_units,
+      "entities": self.entities,
+      "counts": &self.counts,
+      "parameters_per_function": average(self.parameters),
+      "lines_per_function": average(self.function_lines),
+      "longest_function": self.longest.as_ref().map(|(name, lines)| serde_json::json!({ "name": name, "lines": lines })),
+      "max_depth": self.max_depth
+    }));
+    pb_print!("☑️ {:<24} {}", "translation units".bold(), self.translation_units.to_string().bold().green());
+    pb_print!("☑️ {:<24} {}", "entities".bold(), self.entities.to_string().bold().green());
+    for (group, count) in &self.counts {
+      pb_print!("☑️ {:<24} {}", group.bold(), count.to_string().bold().green());
+    }
+    pb_print!("☑️ {:<24} {:.1}", "parameters per function".bold(), average(self.parameters));
+    pb_print!("☑️ {:<24} {:.1}", "lines per function".bold(), average(self.function_lines));
+    if let Some((name, lines)) = &self.longest {
+      pb_print!("☑️ {:<24} {} ({} lines)", "longest function".bold(), name.bold().green(), lines);
+    }
+    pb_print!("☑️ {:<24} {}", "maximum nesting depth".bold(), self.max_depth.to_string().bold().green());
+    *self = Metrics::default();
+    Ok(())
+  }
+
+  fn begin_translation_unit(&mut self, _opt: &crate::parser::CompileOption) -> anyhow::Result<()>
+  {
+    self.translation_units += 1;
+    Ok(())
+  }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    self.depth += 1;
+    self.max_depth = self.max_depth.max(self.depth - 1);
+    self.entities += 1;
+    if !entity.is_declaration() || is_local(entity) {
+      return VisitFlags::empty();
+    }
+    if let Some(decl) = Decl::from_entity(entity) {
+      match decl.kind {
+        DeclKind::Function { parameters, .. } => {
+          let lines = match entity.is_definition() {
+            true => entity.get_range().map(|range| range.get_end().get_file_location().line - range.get_start().get_file_location().line + 1),
+            false => None
+          };
+          self.function(decl.qualified_name, parameters.len(), lines)
+        },
+        kind => self.count(Self::group(&kind))
+      }
+    }
+    VisitFlags::empty()
+  }
+
+  fn leave_entity(&mut self, _entity: &clang::Entity)
+  {
+    self.depth -= 1;
+  }
+}
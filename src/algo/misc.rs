@@ -1,22 +1,94 @@
 use colored::Colorize;
+use crate::core::output;
+use crate::parser::{kind_name, qualified_name, signature, Decl, Location, VisitFlags, Visitor};
 use crate::pb_print;
 
-pub fn ast_dump(entity: &clang::Entity) -> bool
+// entity as emitted in structured output
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EntityRecord
 {
-  pb_print!("  [{:^24}] {:<50} in file <{}>",
-    format!("{:?}", entity.get_kind()).bold(),
-    entity.get_name().unwrap_or("<unknown>".to_string()).bold().green(),
-    match entity.get_location() {
-      Some(loc) => {
-        let loc = loc.get_file_location();
-        let file_str = match loc.file {
-          Some(file) => format!("{}", file.get_path().file_name().unwrap().to_os_string().into_string().unwrap()),
-          None => "unknown".to_string()
-        };
-        format!("{}:{}:{}", file_str.bold().magenta(), loc.line.to_string().italic(), loc.column.to_string().italic())
-      },
-      None => "unknown".to_string().bold().magenta().to_string()
+  pub kind: String,
+  pub name: String,
+  pub qualified_name: String,
+  #[serde(skip_serializing_if = "Option::is_none")] pub signature: Option<String>,
+  pub usr: Option<String>,
+  pub location: Location
+}
+
+impl EntityRecord
+{
+  pub fn from_entity(entity: &clang::Entity) -> Self
+  {
+    EntityRecord {
+      kind: kind_name(entity),
+      name: entity.get_name().unwrap_or_default(),
+      qualified_name: qualified_name(entity),
+      signature: signature(entity),
+      usr: entity.get_usr().map(|usr| usr.0),
+      location: Location::from_entity(entity)
+    }
+  }
+
+  pub fn from_decl(decl: &Decl) -> Self
+  {
+    EntityRecord {
+      kind: decl.entity_kind.clone(),
+      name: decl.name.clone(),
+      qualified_name: decl.qualified_name.clone(),
+      signature: decl.signature.clone(),
+      usr: decl.usr.clone(),
+      location: decl.location.clone()
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct AstDump;
+
+impl Visitor for AstDump
+{
+  fn enter_entity(&mut self, entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    if !output::is_text() {
+      output::emit("entity", EntityRecord::from_entity(entity));
+      return VisitFlags::empty();
     }
-  );
-  false
-}
\ No newline at end of file
+    pb_print!("  [{:^24}] {:<50} in file <{}>{}",
+      kind_name(entity).bold(),
+      match entity.get_name() {
+        Some(_) => signature(entity).unwrap_or(qualified_name(entity)),
+        None => "<unknown>".to_string()
+      }.bold().green(),
+      match entity.get_location() {
+        Some(loc) => {
+          let loc = loc.get_file_location();
+          let file_str = match loc.file {
+            Some(file) => file.get_path().file_name().unwrap().to_os_string().into_string().unwrap(),
+            None => "unknown".to_string()
+          };
+          format!("{}:{}:{}", file_str.bold().magenta(), loc.line.to_string().italic(), loc.column.to_string().italic())
+        },
+        None => "unknown".to_string().bold().magenta().to_string()
+      },
+      entity.get_usr().map(|usr| format!(" {}", usr.0.dimmed())).unwrap_or_default()
+    );
+    VisitFlags::empty()
+  }
+}
+
+// visits nothing, for commands which only need the declaration model
+#[derive(Debug, Default)]
+pub struct ModelOnly;
+
+impl Visitor for ModelOnly
+{
+  fn accepts_cached(&self) -> bool
+  {
+    true
+  }
+
+  fn enter_entity(&mut self, _entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    VisitFlags::SKIP_BODY
+  }
+}
@@ -1 +1,7 @@
-pub mod misc;
\ No newline at end of file
+pub mod misc;
+pub mod dump;
+pub mod query;
+pub mod lint;
+pub mod metrics;
+pub mod pipeline;
+pub mod info;
\ No newline at end of file
@@ -0,0 +1,171 @@
+use colored::Colorize;
+use crate::parser::{CompileOption, CompileOptions, Decl, VisitFlags, Visitor};
+use crate::pb_print;
+
+// named algorithm which can be combined with others in a single parse
+pub struct Pass
+{
+  pub name: &'static str,
+  pub description: &'static str,
+  create: fn() -> Box<dyn Visitor>
+}
+
+pub const PASSES: [Pass; 4] = [
+  Pass { name: "dump", description: "print every entity with its kind and location", create: || Box::new(super::misc::AstDump) },
+  Pass { name: "functions", description: "print functions and methods and count them by kind", create: || Box::<crate::doc::mcss::algo::FnDump>::default() },
+  Pass { name: "lint", description: "report common design problems in declarations", create: || Box::<super::lint::Lint>::default() },
+  Pass { name: "metrics", description: "count declarations and measure function sizes", create: || Box::<super::metrics::Metrics>::default() }
+];
+
+pub fn create(name: &str) -> anyhow::Result<Box<dyn Visitor>>
+{
+  match PASSES.iter().find(|pass| pass.name == name) {
+    Some(pass) => Ok((pass.create)()),
+    None => Err(anyhow::anyhow!("unknown pass: {}. available passes:\n{}", name,
+      PASSES.iter().map(|pass| format!("  {:<12}{}", pass.name, pass.description)).collect::<Vec<_>>().join("\n")
+    ))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visibility
+{
+  Visited(VisitFlags),
+  Hidden
+}
+
+struct Slot
+{
+  name: &'static str,
+  visitor: Box<dyn Visitor>,
+  stack: Vec<Visibility>
+}
+
+// feeds every translation unit to several passes at once. each pass sees the tree as if it was the only visitor:
+// children it pruned or bodies it skipped are hidden from it, even if other passes still descend into them
+pub struct Pipeline
+{
+  slots: Vec<Slot>
+}
+
+impl Pipeline
+{
+  pub fn new(names: &[String]) -> anyhow::Result<Self>
+  {
+    anyhow::ensure!(!names.is_empty(), "no passes selected");
+    let slots = names
+      .iter()
+      .map(|name| {
+        let pass = PASSES.iter().find(|pass| pass.name == name).map(|pass| pass.name).unwrap_or_default();
+        Ok(Slot { name: pass, visitor: create(name)?, stack: vec![] })
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Pipeline { slots })
+  }
+}
+
+// flags to walk with, the walk descends wherever at least one pass does
+fn combine(flags: impl Iterator<Item = VisitFlags>) -> VisitFlags
+{
+  flags
+    .map(|flags| match flags.contains(VisitFlags::PRUNE) {
+      true => VisitFlags::PRUNE | VisitFlags::SKIP_BODY,
+      false => flags
+    })
+    .fold(VisitFlags::PRUNE | VisitFlags::SKIP_BODY, |combined, flags| combined & flags)
+}
+
+impl Visitor for Pipeline
+{
+  fn begin_run(&mut self, opts: &CompileOptions) -> anyhow::Result<()>
+  {
+    self.slots.iter_mut().try_for_each(|slot| slot.visitor.begin_run(opts))
+  }
+
+  fn end_run(&mut self) -> anyhow::Result<()>
+  {
+    for slot in &mut self.slots {
+      pb_print!("📋 {} {}", "pass".dimmed(), slot.name.bold().cyan());
+      slot.visitor.end_run()?;
+    }
+    Ok(())
+  }
+
+  fn begin_translation_unit(&mut self, opt: &CompileOption) -> anyhow::Result<()>
+  {
+    self.slots.iter_mut().try_for_each(|slot| slot.visitor.begin_translation_unit(opt))
+  }
+
+  fn end_translation_unit(&mut self, opt: &CompileOption) -> anyhow::Result<()>
+  {
+    self.slots.iter_mut().try_for_each(|slot| slot.visitor.end_translation_unit(opt))
+  }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    let mut visited = vec![];
+    for slot in &mut self.slots {
+      let hidden = match slot.stack.last() {
+        Some(Visibility::Hidden) => true,
+        Some(Visibility::Visited(flags)) => flags.contains(VisitFlags::PRUNE)
+          || (flags.contains(VisitFlags::SKIP_BODY) && entity.get_kind() == clang::EntityKind::CompoundStmt),
+        None => false
+      };
+      let visibility = match hidden {
+        true => Visibility::Hidden,
+        false => Visibility::Visited(slot.visitor.enter_entity(entity, parent))
+      };
+      if let Visibility::Visited(flags) = visibility {
+        visited.push(flags);
+      }
+      slot.stack.push(visibility);
+    }
+    combine(visited.into_iter())
+  }
+
+  fn leave_entity(&mut self, entity: &clang::Entity)
+  {
+    for slot in &mut self.slots {
+      if let Some(Visibility::Visited(_)) = slot.stack.pop() {
+        slot.visitor.leave_entity(entity);
+      }
+    }
+  }
+
+  // cached declarations are reused only if every pass can do with them
+  fn accepts_cached(&self) -> bool
+  {
+    self.slots.iter().all(|slot| slot.visitor.accepts_cached())
+  }
+
+  fn cached_decl(&mut self, decl: &Decl)
+  {
+    self.slots.iter_mut().for_each(|slot| slot.visitor.cached_decl(decl));
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_combine()
+  {
+    let (prune, skip_body, none) = (VisitFlags::PRUNE, VisitFlags::SKIP_BODY, VisitFlags::empty());
+    assert_eq!(combine([prune, prune].into_iter()), prune | skip_body);
+    assert_eq!(combine([prune, skip_body].into_iter()), skip_body);
+    assert_eq!(combine([skip_body, none].into_iter()), none);
+    assert!(combine(std::iter::empty()).contains(prune));
+  }
+
+  #[test]
+  fn test_create()
+  {
+    assert!(Pipeline::new(&["dump".to_string(), "lint".to_string(), "metrics".to_string()]).is_ok());
+    assert!(Pipeline::new(&[]).is_err());
+    assert!(Pipeline::new(&["functions".to_string()]).unwrap().accepts_cached());
+    assert!(!Pipeline::new(&["functions".to_string(), "lint".to_string()]).unwrap().accepts_cached());
+    assert!(create("frobnicate").err().unwrap().to_string().contains("metrics     count declarations"));
+  }
+}
@@ -0,0 +1,53 @@
+use colored::Colorize;
+use crate::algo::misc::EntityRecord;
+use crate::core::output;
+use crate::parser::{VisitFlags, Visitor};
+use crate::pb_print;
+use crate::query::Matcher;
+
+// prints entities matching a query as soon as they are found, or emits them as entity records
+#[derive(Debug)]
+pub struct Query
+{
+  matcher: Matcher,
+  matches: usize
+}
+
+impl Query
+{
+  pub fn new(matcher: Matcher) -> Self
+  {
+    Query { matcher, matches: 0 }
+  }
+}
+
+impl Visitor for Query
+{
+  fn end_run(&mut self) -> anyhow::Result<()>
+  {
+    pb_print!("☑️ {} matches found", self.matches.to_string().bold().green());
+    self.matches = 0;
+    Ok(())
+  }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    if entity.get_kind() == clang::EntityKind::TranslationUnit || !self.matcher.matches(entity) {
+      return VisitFlags::empty();
+    }
+    let found = EntityRecord::from_entity(entity);
+    match output::is_text() {
+      true => pb_print!("{}:{}:{}: [{}] {}{}",
+        found.location.file.as_ref().map(|f| f.display().to_string()).unwrap_or("unknown".to_string()).bold().magenta(),
+        found.location.line,
+        found.location.column,
+        found.kind.bold(),
+        found.signature.as_ref().unwrap_or(&found.qualified_name).bold().green(),
+        found.usr.as_ref().map(|usr| format!(" {}", usr.dimmed())).unwrap_or_default()
+      ),
+      false => output::emit("entity", &found)
+    }
+    self.matches += 1;
+    VisitFlags::empty()
+  }
+}
@@ -0,0 +1,354 @@
+mod ui;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use ratatui::crossterm::event::KeyCode;
+use crate::algo::dump::Node;
+use crate::parser::{CompileOption, CompileOptions, VisitFlags, Visitor};
+
+pub use ui::run;
+
+// translation unit from compile database with its entity tree, if it was parsed in this run
+pub struct Unit
+{
+  pub source: PathBuf,
+  pub root: Option<Node>
+}
+
+// collects entity trees of every parsed translation unit
+#[derive(Default)]
+pub struct Trees
+{
+  pub units: Vec<Unit>,
+  stack: Vec<Node>,
+  root: Option<Node>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus
+{
+  Units,
+  Tree
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input
+{
+  Search,
+  Filter
+}
+
+// visible line of entity tree. path is a list of child indices from translation unit root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row
+{
+  pub path: Vec<usize>,
+  pub depth: usize,
+  pub expandable: bool,
+  pub expanded: bool
+}
+
+// state of the browser, independent of the terminal
+pub struct Browser
+{
+  pub units: Vec<Unit>,
+  pub unit: usize,
+  pub focus: Focus,
+  pub cursor: usize,
+  pub search: String,
+  pub kinds: Vec<String>,
+  pub input: Option<(Input, String)>,
+  pub status: String,
+  expanded: HashSet<Vec<usize>>
+}
+
+impl Visitor for Trees
+{
+  fn begin_run(&mut self, opts: &CompileOptions) -> anyhow::Result<()>
+  {
+    self.units = opts.options
+      .iter()
+      .map(|opt| Unit { source: opt.source.clone(), root: None })
+      .collect();
+    Ok(())
+  }
+
+  fn end_translation_unit(&mut self, opt: &CompileOption) -> anyhow::Result<()>
+  {
+    let root = self.root.take();
+    if let Some(unit) = self.units.iter_mut().find(|unit| unit.source == opt.source) {
+      unit.root = root;
+    }
+    Ok(())
+  }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    self.stack.push(Node::from_entity(entity));
+    VisitFlags::empty()
+  }
+
+  fn leave_entity(&mut self, _entity: &clang::Entity)
+  {
+    let Some(node) = self.stack.pop() else { return };
+    match self.stack.last_mut() {
+      Some(parent) => parent.children.push(node),
+      None => self.root = Some(node)
+    }
+  }
+}
+
+impl Browser
+{
+  pub fn new(units: Vec<Unit>) -> Self
+  {
+    Browser {
+      units,
+      unit: 0,
+      focus: Focus::Units,
+      cursor: 0,
+      search: String::new(),
+      kinds: vec![],
+      input: None,
+      status: String::new(),
+      expanded: HashSet::new()
+    }
+  }
+
+  pub fn root(&self) -> Option<&Node>
+  {
+    self.units.get(self.unit).and_then(|unit| unit.root.as_ref())
+  }
+
+  pub fn node(&self, path: &[usize]) -> Option<&Node>
+  {
+    path.iter().try_fold(self.root()?, |node, i| node.children.get(*i))
+  }
+
+  pub fn selected(&self) -> Option<&Node>
+  {
+    let rows = self.rows();
+    rows.get(self.cursor).and_then(|row| self.node(&row.path))
+  }
+
+  pub fn is_filtered(&self) -> bool
+  {
+    !self.search.is_empty() || !self.kinds.is_empty()
+  }
+
+  pub fn matches(&self, node: &Node) -> bool
+  {
+    let search = self.search.to_lowercase();
+    let in_kinds = self.kinds.is_empty() || self.kinds.contains(&node.kind);
+    let in_search = search.is_empty() || [&node.name, &node.qualified_name]
+      .iter()
+      .any(|name| name.as_ref().is_some_and(|name| name.to_lowercase().contains(&search)));
+    in_kinds && in_search
+  }
+
+  // visible rows of the tree. filtered trees show matching entities together with their parents, fully expanded
+  pub fn rows(&self) -> Vec<Row>
+  {
+    let mut rows = vec![];
+    if let Some(root) = self.root() {
+      for (i, child) in root.children.iter().enumerate() {
+        self.collect_rows(child, vec![i], &mut rows);
+      }
+    }
+    rows
+  }
+
+  // adds rows of node and its visible children, returns whether anything was added
+  fn collect_rows(&self, node: &Node, path: Vec<usize>, rows: &mut Vec<Row>) -> bool
+  {
+    let at = rows.len();
+    let depth = path.len() - 1;
+    let expanded = self.is_filtered() || self.expanded.contains(&path);
+    rows.push(Row { path: path.clone(), depth, expandable: !node.children.is_empty(), expanded });
+    let mut any_child = false;
+    if expanded {
+      for (i, child) in node.children.iter().enumerate() {
+        let mut child_path = path.clone();
+        child_path.push(i);
+        any_child |= self.collect_rows(child, child_path, rows);
+      }
+    }
+    if self.is_filtered() && !any_child && !self.matches(node) {
+      rows.truncate(at);
+      return false;
+    }
+    true
+  }
+
+  // applies a key press, returns false to quit
+  pub fn handle(&mut self, key: KeyCode) -> bool
+  {
+    if let Some((input, mut text)) = self.input.take() {
+      match key {
+        KeyCode::Enter => self.apply(input, &text),
+        KeyCode::Esc => {},
+        KeyCode::Backspace => {
+          text.pop();
+          self.input = Some((input, text));
+        },
+        KeyCode::Char(c) => {
+          text.push(c);
+          self.input = Some((input, text));
+        },
+        _ => self.input = Some((input, text))
+      }
+      return true;
+    }
+    let rows = match self.focus {
+      Focus::Units => self.units.len(),
+      Focus::Tree => self.rows().len()
+    };
+    match key {
+      KeyCode::Char('q') | KeyCode::Esc => return false,
+      KeyCode::Tab => self.focus = match self.focus {
+        Focus::Units => Focus::Tree,
+        Focus::Tree => Focus::Units
+      },
+      KeyCode::Char('/') => self.input = Some((Input::Search, self.search.clone())),
+      KeyCode::Char('f') => self.input = Some((Input::Filter, self.kinds.join(","))),
+      KeyCode::Char('c') => {
+        self.search.clear();
+        self.kinds.clear();
+        self.cursor = 0;
+        self.status.clear();
+      },
+      KeyCode::Up | KeyCode::Char('k') => self.move_by(-1, rows),
+      KeyCode::Down | KeyCode::Char('j') => self.move_by(1, rows),
+      KeyCode::PageUp => self.move_by(-20, rows),
+      KeyCode::PageDown => self.move_by(20, rows),
+      KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => match self.focus {
+        Focus::Units => self.focus = Focus::Tree,
+        Focus::Tree => self.expand(true)
+      },
+      KeyCode::Left | KeyCode::Char('h') => match self.focus {
+        Focus::Units => {},
+        Focus::Tree => self.expand(false)
+      },
+      _ => {}
+    }
+    true
+  }
+
+  fn move_by(&mut self, delta: isize, rows: usize)
+  {
+    let max = rows.saturating_sub(1) as isize;
+    match self.focus {
+      Focus::Units => {
+        let unit = (self.unit as isize + delta).clamp(0, max) as usize;
+        if unit != self.unit {
+          self.unit = unit;
+          self.cursor = 0;
+          self.expanded.clear();
+        }
+      },
+      Focus::Tree => self.cursor = (self.cursor as isize + delta).clamp(0, max) as usize
+    }
+  }
+
+  // expands or collapses selected entity. collapsing a collapsed entity selects its parent
+  fn expand(&mut self, expand: bool)
+  {
+    let rows = self.rows();
+    let Some(row) = rows.get(self.cursor) else { return };
+    match (expand, row.expanded) {
+      (true, _) => { self.expanded.insert(row.path.clone()); },
+      (false, true) if !self.is_filtered() => { self.expanded.remove(&row.path); },
+      (false, _) => {
+        let parent = &row.path[..row.path.len() - 1];
+        if let Some(i) = rows.iter().position(|r| r.path == parent) {
+          self.cursor = i;
+        }
+      }
+    }
+  }
+
+  fn apply(&mut self, input: Input, text: &str)
+  {
+    self.status.clear();
+    self.cursor = 0;
+    match input {
+      Input::Search => self.search = text.trim().to_string(),
+      Input::Filter => {
+        let names = text
+          .split(',')
+          .map(str::trim)
+          .filter(|name| !name.is_empty())
+          .map(str::to_string)
+          .collect::<Vec<_>>();
+        match crate::parser::parse_kinds(&names) {
+          Ok(kinds) => self.kinds = kinds.iter().map(|kind| format!("{:?}", kind)).collect(),
+          Err(e) => self.status = e.to_string()
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn node(kind: &str, name: &str, children: Vec<Node>) -> Node
+  {
+    let mut node = Node::default();
+    node.kind = kind.to_string();
+    node.name = Some(name.to_string());
+    node.children = children;
+    node
+  }
+
+  #[test]
+  fn test_browser()
+  {
+    let root = node("TranslationUnit", "main.cc", vec![
+      node("Namespace", "floppy", vec![
+        node("ClassDecl", "Bar", vec![node("Method", "get_value", vec![])]),
+        node("FunctionDecl", "foo", vec![])
+      ]),
+      node("FunctionDecl", "main", vec![])
+    ]);
+    let mut browser = Browser::new(vec![
+      Unit { source: "main.cc".into(), root: Some(root) },
+      Unit { source: "other.cc".into(), root: None }
+    ]);
+    let names = |browser: &Browser| browser.rows()
+      .iter()
+      .map(|row| browser.node(&row.path).unwrap().name.clone().unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(names(&browser), vec!["floppy", "main"]);
+
+    browser.handle(KeyCode::Enter);
+    assert_eq!(browser.focus, Focus::Tree);
+    browser.handle(KeyCode::Right);
+    assert_eq!(names(&browser), vec!["floppy", "Bar", "foo", "main"]);
+    browser.handle(KeyCode::Down);
+    browser.handle(KeyCode::Left);
+    assert_eq!(browser.cursor, 0);
+    browser.handle(KeyCode::Left);
+    assert_eq!(names(&browser), vec!["floppy", "main"]);
+
+    for key in [KeyCode::Char('/'), KeyCode::Char('v'), KeyCode::Char('a'), KeyCode::Char('l'), KeyCode::Enter] {
+      browser.handle(key);
+    }
+    assert_eq!(names(&browser), vec!["floppy", "Bar", "get_value"]);
+    browser.handle(KeyCode::Char('c'));
+    for key in [KeyCode::Char('f'), KeyCode::Char('f'), KeyCode::Char('u'), KeyCode::Char('n'), KeyCode::Char('c'), KeyCode::Char('t'), KeyCode::Char('i'), KeyCode::Char('o'), KeyCode::Char('n'), KeyCode::Char('s'), KeyCode::Enter] {
+      browser.handle(key);
+    }
+    assert!(browser.status.is_empty());
+    assert_eq!(names(&browser), vec!["floppy", "Bar", "get_value", "foo", "main"]);
+    browser.handle(KeyCode::Char('c'));
+
+    browser.handle(KeyCode::Tab);
+    browser.handle(KeyCode::Down);
+    assert_eq!(browser.unit, 1);
+    assert!(browser.rows().is_empty());
+    assert!(!browser.handle(KeyCode::Char('q')));
+  }
+}
@@ -0,0 +1,179 @@
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListState, Paragraph};
+use ratatui::Frame;
+use crate::algo::dump::Node;
+use super::{Browser, Focus, Input};
+
+const HELP: &str = "tab switch pane · ↑↓ move · →/enter expand · ← collapse · / search · f filter kinds · c clear · q quit";
+
+// takes over the terminal until the browser is closed
+pub fn run(browser: &mut Browser) -> anyhow::Result<()>
+{
+  let mut terminal = ratatui::init();
+  let result = (|| -> anyhow::Result<()> {
+    loop {
+      terminal.draw(|frame| draw(frame, browser))?;
+      if let Event::Key(key) = event::read()? {
+        if key.kind == KeyEventKind::Press && !browser.handle(key.code) {
+          return Ok(());
+        }
+      }
+    }
+  })();
+  ratatui::restore();
+  result
+}
+
+fn draw(frame: &mut Frame, browser: &Browser)
+{
+  let [main, footer] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+  let [units, tree, details] = Layout::horizontal([
+    Constraint::Percentage(25),
+    Constraint::Percentage(40),
+    Constraint::Percentage(35)
+  ]).areas(main);
+  draw_units(frame, browser, units);
+  draw_tree(frame, browser, tree);
+  draw_details(frame, browser, details);
+  let footer_line = match (&browser.input, browser.status.is_empty()) {
+    (Some((Input::Search, text)), _) => Line::from(format!("search: {}", text)),
+    (Some((Input::Filter, text)), _) => Line::from(format!("kinds: {}", text)),
+    (None, false) => Line::from(browser.status.as_str()).red(),
+    (None, true) => Line::from(HELP).dark_gray()
+  };
+  frame.render_widget(Paragraph::new(footer_line), footer);
+}
+
+fn pane(title: String, focused: bool) -> Block<'static>
+{
+  let block = Block::bordered().title(title);
+  match focused {
+    true => block.border_style(Style::new().fg(Color::Green)),
+    false => block
+  }
+}
+
+fn draw_units(frame: &mut Frame, browser: &Browser, area: Rect)
+{
+  let items = browser.units
+    .iter()
+    .map(|unit| {
+      let name = unit.source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+      match unit.root {
+        Some(_) => Line::from(name),
+        None => Line::from(name).dark_gray()
+      }
+    })
+    .collect::<Vec<_>>();
+  let list = List::new(items)
+    .block(pane(format!(" translation units ({}) ", browser.units.len()), browser.focus == Focus::Units))
+    .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+  frame.render_stateful_widget(list, area, &mut ListState::default().with_selected(Some(browser.unit)));
+}
+
+fn draw_tree(frame: &mut Frame, browser: &Browser, area: Rect)
+{
+  let rows = browser.rows();
+  let items = rows
+    .iter()
+    .filter_map(|row| browser.node(&row.path).map(|node| (row, node)))
+    .map(|(row, node)| {
+      let marker = match (row.expandable, row.expanded) {
+        (false, _) => "  ",
+        (true, false) => "▸ ",
+        (true, true) => "▾ "
+      };
+      let name = Span::from(node.name.clone().unwrap_or_default());
+      Line::from(vec![
+        Span::from(format!("{}{}", "  ".repeat(row.depth), marker)),
+        Span::from(format!("{} ", node.kind)).cyan(),
+        match browser.is_filtered() && browser.matches(node) {
+          true => name.bold().yellow(),
+          false => name
+        }
+      ])
+    })
+    .collect::<Vec<_>>();
+  let mut title = format!(" entities ({}) ", rows.len());
+  if !browser.search.is_empty() {
+    title += &format!("search: {} ", browser.search);
+  }
+  if !browser.kinds.is_empty() {
+    title += &format!("kinds: {} ", browser.kinds.len());
+  }
+  let empty = match browser.units.get(browser.unit) {
+    Some(unit) if unit.root.is_none() => "translation unit was not parsed in this run",
+    _ => "no entities"
+  };
+  let block = pane(title, browser.focus == Focus::Tree);
+  if items.is_empty() {
+    frame.render_widget(Paragraph::new(empty).dark_gray().block(block), area);
+    return;
+  }
+  let list = List::new(items)
+    .block(block)
+    .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+  frame.render_stateful_widget(list, area, &mut ListState::default().with_selected(Some(browser.cursor)));
+}
+
+fn draw_details(frame: &mut Frame, browser: &Browser, area: Rect)
+{
+  let [details, source] = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+  let Some(node) = browser.selected() else {
+    frame.render_widget(Block::bordered().title(" details "), details);
+    frame.render_widget(Block::bordered().title(" source "), source);
+    return;
+  };
+  let field = |key: &str, value: &Option<String>| value.as_ref().map(|value| Line::from(vec![
+    Span::from(format!("{:<15}", key)).dark_gray(),
+    Span::from(value.clone())
+  ]));
+  let attributes = node.attributes
+    .iter()
+    .map(|attr| attr.spelling.clone().unwrap_or(attr.kind.clone()))
+    .collect::<Vec<_>>();
+  let range = node.range.as_ref().map(|range| format!("{}:{}:{}-{}:{}",
+    range.file.as_ref().map(|f| f.display().to_string()).unwrap_or("unknown".to_string()),
+    range.start.line, range.start.column, range.end.line, range.end.column
+  ));
+  let lines = [
+    field("kind", &Some(node.kind.clone())),
+    field("name", &node.name),
+    field("qualified name", &node.qualified_name),
+    field("signature", &node.signature),
+    field("type", &node.type_name),
+    field("canonical type", &node.canonical_type),
+    field("usr", &node.usr),
+    field("access", &node.access),
+    field("storage class", &node.storage_class),
+    field("attributes", &(!attributes.is_empty()).then(|| attributes.join(", "))),
+    field("location", &range)
+  ].into_iter().flatten().collect::<Vec<_>>();
+  frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" details ")), details);
+  frame.render_widget(Paragraph::new(snippet(node, source.height.saturating_sub(2) as u32)).block(Block::bordered().title(" source ")), source);
+}
+
+// source lines of the entity range, prefixed with line numbers. lines inside the range are highlighted
+fn snippet(node: &Node, height: u32) -> Vec<Line<'static>>
+{
+  let Some(range) = &node.range else { return vec![] };
+  let Some(contents) = range.file.as_ref().and_then(|file| std::fs::read_to_string(file).ok()) else { return vec![] };
+  let first = range.start.line.saturating_sub(2).max(1);
+  contents
+    .lines()
+    .enumerate()
+    .map(|(i, line)| (i as u32 + 1, line))
+    .skip(first as usize - 1)
+    .take(height as usize)
+    .map(|(n, line)| {
+      let number = Span::from(format!("{:>5} ", n)).dark_gray();
+      match (range.start.line..=range.end.line).contains(&n) {
+        true => Line::from(vec![number, Span::from(line.to_string()).white()]),
+        false => Line::from(vec![number, Span::from(line.to_string()).dark_gray()])
+      }
+    })
+    .collect()
+}
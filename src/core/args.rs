@@ -1,3 +1,5 @@
+use crate::core::output::{ColorMode, Format, Progress};
+
 #[derive(clap::Parser, Debug, Clone)]
 #[command(name = "cxt", bin_name = "cxt")]
 #[command(about = "custom preprocessor for c++", long_about = None)]
@@ -8,7 +10,32 @@ pub struct Args
   #[command(subcommand)] pub command: Option<Command>,
 
   /// Print version and exit
-  #[arg(short, long)] pub version: bool
+  #[arg(short = 'V', long)] pub version: bool,
+
+  /// Print only results, warnings and errors
+  #[arg(short, long, global = true, conflicts_with = "verbose")] pub quiet: bool,
+
+  /// Print more details: compile database summary with -v, compiler flags of every translation unit with -vv
+  #[arg(short, long, global = true, action = clap::ArgAction::Count)] pub verbose: u8,
+
+  /// Colorize output. `auto` disables colors when NO_COLOR is set or output is not a terminal
+  #[arg(long, global = true, value_enum, default_value_t = ColorMode::Auto)] pub color: ColorMode,
+
+  /// Progress display. `plain` prints a line per translation unit, for CI logs
+  #[arg(long, global = true, value_enum, default_value_t = Progress::Auto)] pub progress: Progress,
+
+  /// Skip entities of given kinds together with their children. Accepts kind names and groups
+  /// (`functions`, `types`, `templates`, `variables`, `namespaces`, `attributes`, `statements`, `expressions`, `preprocessing`)
+  #[arg(long, global = true, value_delimiter = ',')] pub ignore_kind: Option<Vec<String>>,
+
+  /// Process only entities of given kinds. Accepts the same names as --ignore-kind
+  #[arg(long, global = true, value_delimiter = ',')] pub only_kind: Option<Vec<String>>,
+
+  /// Path to libclang shared library to load. Requires cxt built with the `runtime` feature, which is enabled by default
+  #[arg(long, global = true)] pub libclang: Option<String>,
+
+  /// Output format. Progress and status messages go to stderr in `json` and `jsonl`
+  #[arg(long, global = true, value_enum, default_value_t = Format::Text)] pub format: Format
 }
 
 #[derive(clap::Subcommand, Debug, Clone)]
@@ -18,7 +45,25 @@ pub enum Command
   Process(ProcessArgs),
 
   /// Generate documentation
-  Doc(DocArgs)
+  Doc(DocArgs),
+
+  /// Dump AST of translation units as JSON or S-expression trees
+  Dump(DumpArgs),
+
+  /// Find entities matching a query, e.g. `function(name=~"^get", returns("bool"), in_namespace("floppy"))`
+  Query(QueryArgs),
+
+  /// Parse once, then query and navigate declarations interactively
+  Repl(ProcessArgs),
+
+  /// Browse translation units and their entity trees in a terminal UI
+  Browse(ProcessArgs),
+
+  /// Parse translation units once and feed them to several passes, each producing its own report
+  Run(RunArgs),
+
+  /// Inspect compile database entries and arguments passed to libclang for them
+  Info(InfoArgs)
 }
 
 #[derive(clap::Args, Debug, Clone)]
@@ -31,7 +76,49 @@ pub struct ProcessArgs
   #[arg(short='I', long)] pub include_flags: Option<Vec<String>>,
 
   /// Ignore tests
-  #[arg(long)] pub ignore_tests: bool
+  #[arg(long)] pub ignore_tests: bool,
+
+  /// Cache directory for incremental processing
+  #[arg(long)] pub cache_dir: Option<String>,
+
+  /// Merge declaration and definition sites of the same entity into one record
+  #[arg(long)] pub merge_definitions: bool,
+
+  /// libclang parse flags: `full`, `fast`, `skip-function-bodies`, `incomplete`,
+  /// `detailed-preprocessing-record`, `keep-going`, `single-file-parse`
+  #[arg(long, value_delimiter = ',', default_value = "full")] pub parse_flags: Vec<String>,
+
+  /// Precompile includes shared by translation units with identical flags and reuse them
+  #[arg(long)] pub pch: bool,
+
+  /// Save parsed translation units into cache directory and reload them in later runs
+  #[arg(long)] pub persist_ast: bool,
+
+  /// JSON file with a map of paths to unsaved file contents, used instead of files on disk
+  #[arg(long)] pub unsaved: Option<String>,
+
+  /// Read contents of given file from stdin instead of disk
+  #[arg(long)] pub stdin_file: Option<String>,
+
+  /// Process only files affected by changes in git working tree since given revision
+  #[arg(long)] pub changed_since: Option<String>,
+
+  /// Keep running and re-process translation units affected by changes to their files or to the compile database
+  #[arg(long)] pub watch: bool,
+
+  /// Print the slowest translation units with their parse and traversal times, entity counts and peak memory
+  #[arg(long)] pub profile: bool,
+
+  /// Write per translation unit timings as Chrome trace-event JSON into given file
+  #[arg(long)] pub profile_trace: Option<String>,
+
+  /// Clang resource directory with builtin headers. Detected from loaded libclang by default
+  #[arg(long)] pub resource_dir: Option<String>,
+
+  /// Do not add system include paths reported by compilers from compile database
+  #[arg(long)] pub skip_system_probe: bool,
+
+  #[command(flatten)] pub scope: ScopeArgs
 }
 
 #[derive(clap::Args, Debug, Clone)]
@@ -46,11 +133,123 @@ pub struct DocArgs
   /// Ignore tests
   #[arg(long)] pub ignore_tests: bool,
 
-  /// Output format. Can be `markdown` or `m.css`
-  #[arg(short, long, default_value_t = String::from("m.css"))] pub format: String,
+  /// Documentation flavor. Can be `markdown` or `m.css`. Was `--format` before the global output format
+  #[arg(short = 'f', long, default_value_t = String::from("m.css"))] pub doc_format: String,
 
   /// Output directory
-  #[arg(long)] pub output: Option<String>
+  #[arg(long)] pub output: Option<String>,
+
+  /// Cache directory for incremental processing
+  #[arg(long)] pub cache_dir: Option<String>,
+
+  /// Merge declaration and definition sites of the same entity into one record
+  #[arg(long)] pub merge_definitions: bool,
+
+  /// libclang parse flags. Same as for `process`, but defaults to the fastest mode
+  #[arg(long, value_delimiter = ',', default_value = "fast")] pub parse_flags: Vec<String>,
+
+  /// Precompile includes shared by translation units with identical flags and reuse them
+  #[arg(long)] pub pch: bool,
+
+  /// Save parsed translation units into cache directory and reload them in later runs
+  #[arg(long)] pub persist_ast: bool,
+
+  /// JSON file with a map of paths to unsaved file contents, used instead of files on disk
+  #[arg(long)] pub unsaved: Option<String>,
+
+  /// Read contents of given file from stdin instead of disk
+  #[arg(long)] pub stdin_file: Option<String>,
+
+  /// Process only files affected by changes in git working tree since given revision
+  #[arg(long)] pub changed_since: Option<String>,
+
+  /// Keep running and re-process translation units affected by changes to their files or to the compile database
+  #[arg(long)] pub watch: bool,
+
+  /// Print the slowest translation units with their parse and traversal times, entity counts and peak memory
+  #[arg(long)] pub profile: bool,
+
+  /// Write per translation unit timings as Chrome trace-event JSON into given file
+  #[arg(long)] pub profile_trace: Option<String>,
+
+  /// Clang resource directory with builtin headers. Detected from loaded libclang by default
+  #[arg(long)] pub resource_dir: Option<String>,
+
+  /// Do not add system include paths reported by compilers from compile database
+  #[arg(long)] pub skip_system_probe: bool,
+
+  #[command(flatten)] pub scope: ScopeArgs
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct DumpArgs
+{
+  #[command(flatten)] pub process: ProcessArgs,
+
+  /// Tree syntax. Can be `json` or `sexp`
+  #[arg(long, default_value_t = String::from("json"))] pub syntax: String,
+
+  /// Dump only entities from files whose path ends with given one
+  #[arg(long)] pub file: Option<String>,
+
+  /// Dump only entities overlapping given line range, e.g. `10-20` or `15`
+  #[arg(long)] pub lines: Option<String>,
+
+  /// Dump only entities whose name or qualified name matches given regex, together with their parents
+  #[arg(long)] pub name: Option<String>,
+
+  /// Maximum depth of dumped trees below translation unit
+  #[arg(long)] pub max_depth: Option<usize>,
+
+  /// Output file. Written to stdout by default, or emitted as `tree` records with --format json or jsonl
+  #[arg(short, long)] pub output: Option<String>
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct QueryArgs
+{
+  /// Query expression. Node matchers (`function`, `method`, `class`, `struct`, `enum`, `namespace`, `variable`, `field`, ...)
+  /// take predicates (`name=~"regex"`, `returns("bool")`, `in_namespace("ns")`, `derives("Base")`, `is_virtual`, `!is_override`, ...)
+  pub query: String,
+
+  #[command(flatten)] pub process: ProcessArgs
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RunArgs
+{
+  #[command(flatten)] pub process: ProcessArgs,
+
+  /// Passes to run, in order of their reports: `dump`, `functions`, `lint`, `metrics`
+  #[arg(short, long = "pass", value_delimiter = ',', required = true)] pub passes: Vec<String>
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct InfoArgs
+{
+  #[command(flatten)] pub process: ProcessArgs,
+
+  /// Show entries for sources whose path ends with given components instead of a summary
+  #[arg(long)] pub file: Option<String>
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ScopeArgs
+{
+  /// Project source roots. Entities from files outside of them are skipped
+  #[arg(long)] pub source_root: Option<Vec<String>>,
+
+  /// Third-party directories, skipped unless --include-third-party is set
+  #[arg(long)] pub third_party: Option<Vec<String>>,
+
+  /// Process entities from files in the build directory
+  #[arg(long)] pub include_build_dir: bool,
+
+  /// Process entities from third-party directories
+  #[arg(long)] pub include_third_party: bool,
+
+  /// Process entities from main files of translation units only
+  #[arg(long)] pub main_file_only: bool
 }
 
 impl From<&DocArgs> for ProcessArgs
@@ -61,7 +260,21 @@ impl From<&DocArgs> for ProcessArgs
     {
       input: args.input.clone(),
       include_flags: args.include_flags.clone(),
-      ignore_tests: args.ignore_tests
+      ignore_tests: args.ignore_tests,
+      cache_dir: args.cache_dir.clone(),
+      merge_definitions: args.merge_definitions,
+      parse_flags: args.parse_flags.clone(),
+      pch: args.pch,
+      persist_ast: args.persist_ast,
+      unsaved: args.unsaved.clone(),
+      stdin_file: args.stdin_file.clone(),
+      changed_since: args.changed_since.clone(),
+      watch: args.watch,
+      profile: args.profile,
+      profile_trace: args.profile_trace.clone(),
+      resource_dir: args.resource_dir.clone(),
+      skip_system_probe: args.skip_system_probe,
+      scope: args.scope.clone()
     }
   }
 }
\ No newline at end of file
@@ -4,9 +4,35 @@ use crate::core::names::{NAME, VERSION};
 #[macro_export]
 macro_rules! pb_print {
   ($($arg:tt)*) => {{
-    println!();
-    print!("\x1b[A\x1b[2K\r");
-    println!("  {}", format_args!($($arg)*));
+    if !$crate::core::output::messages_on_stdout() {
+      eprintln!("  {}", format_args!($($arg)*));
+    } else if $crate::core::output::progress() == $crate::core::output::Progress::Bar {
+      println!();
+      print!("\x1b[A\x1b[2K\r");
+      println!("  {}", format_args!($($arg)*));
+    } else {
+      println!("  {}", format_args!($($arg)*));
+    }
+  }}
+}
+
+// status message, hidden with --quiet
+#[macro_export]
+macro_rules! pb_status {
+  ($($arg:tt)*) => {{
+    if $crate::core::output::verbosity() >= 0 {
+      $crate::pb_print!($($arg)*);
+    }
+  }}
+}
+
+// detail message, shown with -vv
+#[macro_export]
+macro_rules! pb_debug {
+  ($($arg:tt)*) => {{
+    if $crate::core::output::verbosity() >= 2 {
+      $crate::pb_print!($($arg)*);
+    }
   }}
 }
 
@@ -1,5 +1,6 @@
 pub mod args;
 pub mod names;
 pub mod cli;
+pub mod output;
 
 pub use args::Args;
\ No newline at end of file
@@ -0,0 +1,156 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Format
+{
+  /// Human readable messages with colors and progress bar
+  #[default] Text,
+  /// Single JSON array of records printed at exit
+  Json,
+  /// One JSON record per line, printed as soon as it is produced
+  Jsonl
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorMode
+{
+  /// Colors on terminals, unless NO_COLOR is set
+  #[default] Auto,
+  Always,
+  Never
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Progress
+{
+  /// Progress bar on terminals, plain lines when CI is set, nothing otherwise
+  #[default] Auto,
+  /// Progress bar redrawn in place
+  Bar,
+  /// One line per processed translation unit, for CI logs
+  Plain,
+  /// No progress at all
+  None
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Settings
+{
+  format: Format,
+  verbosity: i8,
+  progress: Progress
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+static RECORDS: Mutex<Vec<serde_json::Value>> = Mutex::new(vec![]);
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+// verbosity is -1 for quiet output, 0 by default and 1 or 2 for more details
+pub fn init(format: Format, verbosity: i8, color: ColorMode, progress: Progress)
+{
+  match color {
+    ColorMode::Always => colored::control::set_override(true),
+    ColorMode::Never => colored::control::set_override(false),
+    ColorMode::Auto if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) => colored::control::set_override(false),
+    ColorMode::Auto => {}
+  }
+  let progress = match progress {
+    Progress::Auto if verbosity < 0 || format != Format::Text => Progress::None,
+    Progress::Auto if std::env::var_os("CI").is_some() => Progress::Plain,
+    Progress::Auto if std::io::stdout().is_terminal() => Progress::Bar,
+    Progress::Auto => Progress::None,
+    progress => progress
+  };
+  let _ = SETTINGS.set(Settings { format, verbosity, progress });
+}
+
+fn settings() -> Settings
+{
+  SETTINGS.get().copied().unwrap_or_default()
+}
+
+pub fn format() -> Format
+{
+  settings().format
+}
+
+pub fn is_text() -> bool
+{
+  format() == Format::Text
+}
+
+pub fn verbosity() -> i8
+{
+  settings().verbosity
+}
+
+// moves text messages and progress to stderr, for commands which write their results to stdout
+pub fn reserve_stdout()
+{
+  STDOUT_RESERVED.store(true, Ordering::Relaxed);
+}
+
+// whether messages go to stdout. structured formats keep stdout for records
+pub fn messages_on_stdout() -> bool
+{
+  is_text() && !STDOUT_RESERVED.load(Ordering::Relaxed)
+}
+
+// resolved progress mode, never auto
+pub fn progress() -> Progress
+{
+  match settings().progress {
+    Progress::Auto => Progress::None,
+    progress => progress
+  }
+}
+
+// structured record tagged with its type in `record` field. ignored in text format
+pub fn emit(record: &str, value: impl serde::Serialize)
+{
+  if is_text() { return }
+  let tagged = tag(record, value);
+  match format() {
+    Format::Jsonl => println!("{}", tagged),
+    _ => RECORDS.lock().unwrap().push(tagged)
+  }
+}
+
+// fields of serialized object with `record` prepended. other values are put into `value` field
+fn tag(record: &str, value: impl serde::Serialize) -> serde_json::Value
+{
+  let mut tagged = serde_json::Map::new();
+  tagged.insert("record".to_string(), record.into());
+  match serde_json::to_value(value) {
+    Ok(serde_json::Value::Object(fields)) => tagged.extend(fields),
+    Ok(value) => { tagged.insert("value".to_string(), value); },
+    Err(e) => { tagged.insert("error".to_string(), e.to_string().into()); }
+  }
+  serde_json::Value::Object(tagged)
+}
+
+// writes records collected in json format
+pub fn finish() -> anyhow::Result<()>
+{
+  if format() == Format::Json {
+    let records = std::mem::take(&mut *RECORDS.lock().unwrap());
+    println!("{}", serde_json::to_string_pretty(&records)?);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_tag()
+  {
+    assert_eq!(tag("summary", serde_json::json!({ "declarations": 3 })), serde_json::json!({ "record": "summary", "declarations": 3 }));
+    assert_eq!(tag("count", 3), serde_json::json!({ "record": "count", "value": 3 }));
+    assert_eq!(format(), Format::Text);
+  }
+}
@@ -1,37 +1,149 @@
+use std::collections::BTreeMap;
 use colored::Colorize;
+use crate::algo::misc::EntityRecord;
+use crate::core::output;
+use crate::parser::{CompileOptions, Decl, VisitFlags, Visitor};
 use crate::pb_print;
 
-const ALLOWED_KINDS: [clang::EntityKind; 4] = [
+const FUNCTION_KINDS: [clang::EntityKind; 3] = [
   clang::EntityKind::FunctionTemplate,
   clang::EntityKind::FunctionDecl,
-  clang::EntityKind::Method,
-  clang::EntityKind::AnnotateAttr
+  clang::EntityKind::Method
 ];
 
-pub fn fn_dump(entity: &clang::Entity) -> bool
+// dumps functions, methods and their annotations and counts them by kind.
+// parsed entities go through the same declarations as cached ones, so both print the same records
+#[derive(Debug, Default)]
+pub struct FnDump
 {
-  if !ALLOWED_KINDS.contains(&entity.get_kind()) { return false; }
-
-  pb_print!("  [{:^10}] {:<30} in file <{:<25}>",
-    format!("{:^8}", match entity.get_kind() {
-      clang::EntityKind::FunctionTemplate => "template".to_string().bright_magenta(),
-      clang::EntityKind::FunctionDecl => "function".to_string().bright_blue(),
-      clang::EntityKind::Method => "method".to_string().bright_cyan(),
-      clang::EntityKind::AnnotateAttr => "attribute".to_string().bright_green(),
-      _ => "unknown".to_string().bright_red()
-    }).bold(),
-    entity.get_name().unwrap_or("<unknown>".to_string()).bold().green(),
-    match entity.get_location() {
-      Some(loc) => {
-        let loc = loc.get_file_location();
-        let file_str = match loc.file {
-          Some(file) => format!("{}", file.get_path().file_name().unwrap().to_os_string().into_string().unwrap()),
-          None => "unknown".to_string()
-        };
-        format!("{}:{}:{}", file_str.bold().magenta(), loc.line.to_string().italic(), loc.column.to_string().italic())
-      },
-      None => "unknown".to_string().bold().magenta().to_string()
+  counts: BTreeMap<String, usize>
+}
+
+impl Visitor for FnDump
+{
+  // counts are per run, watch rounds start from zero
+  fn begin_run(&mut self, _opts: &CompileOptions) -> anyhow::Result<()>
+  {
+    self.counts.clear();
+    Ok(())
+  }
+
+  fn end_run(&mut self) -> anyhow::Result<()>
+  {
+    output::emit("function_counts", serde_json::json!({ "counts": &self.counts }));
+    for (kind, count) in &self.counts {
+      pb_print!("☑️ {:<20} {}", kind.bold(), count.to_string().bold().green());
+    }
+    Ok(())
+  }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+  {
+    if !FUNCTION_KINDS.contains(&entity.get_kind()) { return VisitFlags::SKIP_BODY; }
+
+    if let Some(decl) = Decl::from_entity(entity) {
+      self.function(&decl);
+    }
+    VisitFlags::SKIP_BODY
+  }
+
+  fn accepts_cached(&self) -> bool
+  {
+    true
+  }
+
+  fn cached_decl(&mut self, decl: &Decl)
+  {
+    if FUNCTION_KINDS.iter().any(|kind| format!("{:?}", kind) == decl.entity_kind) {
+      self.function(decl);
     }
-  );
-  true
-}
\ No newline at end of file
+  }
+}
+
+impl FnDump
+{
+  // annotations are not declarations, so they are reported at the function they belong to
+  fn function(&mut self, decl: &Decl)
+  {
+    self.found(EntityRecord::from_decl(decl));
+    for attribute in decl.attributes.iter().filter(|attribute| attribute.kind == "AnnotateAttr") {
+      self.found(EntityRecord {
+        kind: attribute.kind.clone(),
+        name: attribute.spelling.clone().unwrap_or_default(),
+        qualified_name: attribute.spelling.clone().unwrap_or_default(),
+        signature: None,
+        usr: None,
+        location: decl.location.clone()
+      });
+    }
+  }
+
+  fn found(&mut self, record: EntityRecord)
+  {
+    *self.counts.entry(record.kind.clone()).or_default() += 1;
+    if !output::is_text() {
+      output::emit("entity", record);
+      return;
+    }
+    pb_print!("  [{:^10}] {:<30} in file <{:<25}>{}",
+      format!("{:^8}", match record.kind.as_str() {
+        "FunctionTemplate" => "template".to_string().bright_magenta(),
+        "FunctionDecl" => "function".to_string().bright_blue(),
+        "Method" => "method".to_string().bright_cyan(),
+        "AnnotateAttr" => "attribute".to_string().bright_green(),
+        _ => "unknown".to_string().bright_red()
+      }).bold(),
+      match record.name.is_empty() {
+        false => record.signature.unwrap_or(record.qualified_name),
+        true => "<unknown>".to_string()
+      }.bold().green(),
+      match &record.location.file {
+        Some(file) => format!("{}:{}:{}",
+          file.file_name().unwrap().to_os_string().into_string().unwrap().bold().magenta(),
+          record.location.line.to_string().italic(),
+          record.location.column.to_string().italic()
+        ),
+        None => "unknown".to_string().bold().magenta().to_string()
+      },
+      record.usr.map(|usr| format!(" {}", usr.dimmed())).unwrap_or_default()
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use crate::parser::{Attribute, DeclKind};
+
+  #[test]
+  fn test_cached_decl()
+  {
+    let annotate = Attribute { kind: "AnnotateAttr".to_string(), spelling: Some("reflect".to_string()) };
+    let function = Decl {
+      attributes: vec![annotate.clone()],
+      ..Decl::fixture("foo", "FunctionDecl", DeclKind::Function {
+        return_type: "void".to_string(),
+        parameters: vec![],
+        template_parameters: vec![],
+        is_static: false,
+        is_virtual: false,
+        is_pure_virtual: false,
+        is_const: false,
+        is_variadic: false
+      })
+    };
+    let variable = Decl {
+      attributes: vec![annotate],
+      ..Decl::fixture("bar", "VarDecl", DeclKind::Variable { type_name: "int".to_string(), is_static: false })
+    };
+
+    let mut parsed = FnDump::default();
+    parsed.function(&function);
+    let mut cached = FnDump::default();
+    cached.cached_decl(&function);
+    cached.cached_decl(&variable);
+    assert_eq!(cached.counts, parsed.counts);
+    assert_eq!(cached.counts, BTreeMap::from([("AnnotateAttr".to_string(), 1), ("FunctionDecl".to_string(), 1)]));
+  }
+}
@@ -6,6 +6,9 @@ mod core;
 mod parser;
 mod algo;
 mod doc;
+mod query;
+mod repl;
+mod browse;
 
 fn try_main() -> anyhow::Result<()>
 {
@@ -14,21 +17,79 @@ fn try_main() -> anyhow::Result<()>
     core::cli::print_version_and_exit();
   }
 
+  let ignore_kind = args.ignore_kind.as_deref().map(parser::parse_kinds).transpose()?;
+  let only_kind = args.only_kind.as_deref().map(parser::parse_kinds).transpose()?;
+  core::output::init(args.format, if args.quiet { -1 } else { args.verbose.min(2) as i8 }, args.color, args.progress);
+  let verbose = core::output::verbosity() > 0;
+  parser::select_libclang(args.libclang.as_deref())?;
   match &args.command {
     Some(args::Command::Process(argv)) => {
-      let mut parser = parser::Parser::new(argv, false, None)?;
-      parser.parse(argv, algo::misc::ast_dump)?;
+      let mut parser = parser::Parser::new(argv, verbose, ignore_kind, only_kind)?;
+      parser.parse(argv, &mut algo::misc::AstDump)?;
+      if argv.watch {
+        parser.watch(argv, &mut algo::misc::AstDump, &mut |_| Ok(()))?;
+      }
     },
     Some(args::Command::Doc(argv)) => {
       let argv2 = args::ProcessArgs::from(argv);
-      let mut parser = parser::Parser::new(&argv2, false, None)?;
-      parser.parse(&argv2, doc::mcss::algo::fn_dump)?;
+      let mut parser = parser::Parser::new(&argv2, verbose, ignore_kind, only_kind)?;
+      let mut visitor = doc::mcss::algo::FnDump::default();
+      let mut write_model = |model: &parser::Model| -> anyhow::Result<()> {
+        if let Some(output) = &argv.output {
+          std::fs::create_dir_all(output)?;
+          std::fs::write(std::path::Path::new(output).join("model.json"), serde_json::to_string_pretty(model)?)?;
+        }
+        Ok(())
+      };
+      write_model(parser.parse(&argv2, &mut visitor)?)?;
+      if argv.watch {
+        parser.watch(&argv2, &mut visitor, &mut write_model)?;
+      }
+    }
+    Some(args::Command::Dump(argv)) => {
+      let mut dump = algo::dump::TreeDump::new(argv)?;
+      let mut parser = parser::Parser::new(&argv.process, verbose, ignore_kind, only_kind)?;
+      parser.parse(&argv.process, &mut dump)?;
+      if argv.process.watch {
+        parser.watch(&argv.process, &mut dump, &mut |_| Ok(()))?;
+      }
+    }
+    Some(args::Command::Query(argv)) => {
+      let mut query = algo::query::Query::new(query::Matcher::from_query(&argv.query)?);
+      let mut parser = parser::Parser::new(&argv.process, verbose, ignore_kind, only_kind)?;
+      parser.parse(&argv.process, &mut query)?;
+      if argv.process.watch {
+        parser.watch(&argv.process, &mut query, &mut |_| Ok(()))?;
+      }
+    }
+    Some(args::Command::Repl(argv)) => {
+      let mut parser = parser::Parser::new(argv, verbose, ignore_kind, only_kind)?;
+      let model = parser.parse(argv, &mut algo::misc::ModelOnly)?;
+      repl::Repl::new(model).run()?;
+    }
+    Some(args::Command::Browse(argv)) => {
+      let mut parser = parser::Parser::new(argv, verbose, ignore_kind, only_kind)?;
+      let mut trees = browse::Trees::default();
+      parser.parse(argv, &mut trees)?;
+      browse::run(&mut browse::Browser::new(trees.units))?;
+    }
+    Some(args::Command::Run(argv)) => {
+      let mut pipeline = algo::pipeline::Pipeline::new(&argv.passes)?;
+      let mut parser = parser::Parser::new(&argv.process, verbose, ignore_kind, only_kind)?;
+      parser.parse(&argv.process, &mut pipeline)?;
+      if argv.process.watch {
+        parser.watch(&argv.process, &mut pipeline, &mut |_| Ok(()))?;
+      }
+    }
+    Some(args::Command::Info(argv)) => {
+      let parser = parser::Parser::new(&argv.process, verbose, ignore_kind, only_kind)?;
+      algo::info::print(&parser, argv, verbose)?;
     }
     None => {
       return Err(anyhow::anyhow!("no command specified. see --help"));
     }
   }
-  Ok(())
+  core::output::finish()
 }
 
 fn main()
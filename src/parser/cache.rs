@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::parser::model::{Location, Model};
+use crate::parser::opts::CompileOption;
+
+// bumped whenever cached models change, so that entries written by older versions are not reused
+pub const CACHE_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CacheEntry
+{
+  pub source: PathBuf,
+  pub flags: u64,
+  pub files: Vec<(PathBuf, u64)>,
+  pub model: Model,
+  // keys of every entity with usr found in the translation unit, used to skip them in the following ones
+  pub keys: Vec<(String, Location)>
+}
+
+pub struct Cache
+{
+  dir: PathBuf
+}
+
+impl CacheEntry
+{
+  // entry is valid only if flags are the same and neither the source nor any included header changed
+  pub fn is_up_to_date(&self, flags: u64) -> bool
+  {
+    self.flags == flags && self.files
+      .iter()
+      .all(|(path, hash)| hash_file(path).is_ok_and(|h| h == *hash))
+  }
+}
+
+impl Cache
+{
+  pub fn new(dir: &Path) -> anyhow::Result<Self>
+  {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| anyhow::anyhow!("failed to create cache directory {}: {}", dir.display(), e))?;
+    Ok(Cache { dir: dir.to_path_buf() })
+  }
+
+  pub fn load(&self, opt: &CompileOption, flags: u64) -> Option<CacheEntry>
+  {
+    let entry = self.read(opt)?;
+    match entry.source == opt.source && entry.is_up_to_date(flags) {
+      true => Some(entry),
+      false => None
+    }
+  }
+
+  // entry from a previous run, even if it is out of date
+  pub fn read(&self, opt: &CompileOption) -> Option<CacheEntry>
+  {
+    let contents = std::fs::read_to_string(self.entry_path(opt)).ok()?;
+    serde_json::from_str::<CacheEntry>(&contents).ok()
+  }
+
+  pub fn store(&self, opt: &CompileOption, entry: &CacheEntry) -> anyhow::Result<()>
+  {
+    std::fs::write(self.entry_path(opt), serde_json::to_string(entry)?)?;
+    Ok(())
+  }
+
+  // translation unit saved by a previous run, if it is newer than its source and every included header
+  pub fn load_ast<'i>(&self, index: &'i clang::Index<'i>, opt: &CompileOption, flags: u64) -> Option<clang::TranslationUnit<'i>>
+  {
+    let path = self.ast_path(opt, flags);
+    let saved = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    let tu = clang::TranslationUnit::from_ast(index, &path).ok()?;
+    let up_to_date = included_files(&tu, &opt.source)
+      .iter()
+      .all(|file| std::fs::metadata(file).and_then(|m| m.modified()).is_ok_and(|modified| modified <= saved));
+    match up_to_date {
+      true => Some(tu),
+      false => None
+    }
+  }
+
+  pub fn store_ast(&self, tu: &clang::TranslationUnit, opt: &CompileOption, flags: u64) -> anyhow::Result<()>
+  {
+    let path = self.ast_path(opt, flags);
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    tu.save(&path).map_err(|e| anyhow::anyhow!("failed to save {}: {}", path.display(), e))
+  }
+
+  fn entry_path(&self, opt: &CompileOption) -> PathBuf
+  {
+    let hash = StableHasher::default()
+      .field(opt.source.as_os_str().as_encoded_bytes())
+      .field(opt.output.as_os_str().as_encoded_bytes())
+      .finish();
+    self.dir.join(format!("{:016x}.json", hash))
+  }
+
+  fn ast_path(&self, opt: &CompileOption, flags: u64) -> PathBuf
+  {
+    let hash = StableHasher::default()
+      .field(opt.source.as_os_str().as_encoded_bytes())
+      .field(opt.output.as_os_str().as_encoded_bytes())
+      .field(&flags.to_le_bytes())
+      .finish();
+    self.dir.join("ast").join(format!("{:016x}.ast", hash))
+  }
+}
+
+// source file followed by every header it transitively includes
+pub fn included_files(translation_unit: &clang::TranslationUnit, source: &Path) -> Vec<PathBuf>
+{
+  let mut files = vec![source.to_path_buf()];
+  let mut seen = HashSet::from([source.to_path_buf()]);
+  let mut pending = translation_unit.get_file(source).into_iter().collect::<Vec<_>>();
+  while let Some(file) = pending.pop() {
+    for include in file.get_includes() {
+      if let Some(included) = include.get_file() {
+        if seen.insert(included.get_path()) {
+          files.push(included.get_path());
+          pending.push(included);
+        }
+      }
+    }
+  }
+  files
+}
+
+// 64-bit FNV-1a. unlike DefaultHasher, its output doesn't change between Rust releases, so it can be stored on disk
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher
+{
+  fn default() -> Self
+  {
+    StableHasher(0xcbf29ce484222325)
+  }
+}
+
+impl StableHasher
+{
+  pub fn write(&mut self, bytes: &[u8]) -> &mut Self
+  {
+    for byte in bytes {
+      self.0 ^= *byte as u64;
+      self.0 = self.0.wrapping_mul(0x100000001b3);
+    }
+    self
+  }
+
+  // length-prefixed bytes, so that fields `ab`, `c` and `a`, `bc` hash differently
+  pub fn field(&mut self, bytes: &[u8]) -> &mut Self
+  {
+    self.write(&(bytes.len() as u64).to_le_bytes()).write(bytes)
+  }
+
+  pub fn finish(&self) -> u64
+  {
+    self.0
+  }
+}
+
+pub fn hash_flags(flags: &[String]) -> u64
+{
+  flags
+    .iter()
+    .fold(StableHasher::default(), |mut hasher, flag| *hasher.field(flag.as_bytes()))
+    .finish()
+}
+
+pub fn hash_file(path: &Path) -> anyhow::Result<u64>
+{
+  Ok(StableHasher::default().write(&std::fs::read(path)?).finish())
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use crate::parser::model::{Decl, DeclKind, Location};
+
+  #[test]
+  fn test_cache_roundtrip()
+  {
+    let dir = std::env::temp_dir().join(format!("cxt-cache-test-{}", std::process::id()));
+    let source = dir.join("main.cc");
+    let header = dir.join("main.h");
+    let cache = Cache::new(&dir).unwrap();
+    std::fs::write(&source, "#include \"main.h\"\nint main() { return 0; }\n").unwrap();
+    std::fs::write(&header, "int foo();\n").unwrap();
+
+    let opt = CompileOption { source: source.clone(), ..Default::default() };
+    let flags = hash_flags(&["-std=c++20".to_string()]);
+    let mut entry = CacheEntry {
+      source: source.clone(),
+      flags,
+      files: vec![
+        (source.clone(), hash_file(&source).unwrap()),
+        (header.clone(), hash_file(&header).unwrap())
+      ],
+      model: Model::default(),
+      keys: vec![]
+    };
+    entry.model.push(None, Decl {
+      usr: Some("c:@F@main#".to_string()),
+      location: Location { file: Some(source.clone()), line: 2, column: 5 },
+      definition: Some(Location { file: Some(source.clone()), line: 2, column: 5 }),
+      ..Decl::fixture("main", "FunctionDecl", DeclKind::Function {
+        return_type: "int".to_string(),
+        parameters: vec![],
+        template_parameters: vec![],
+        is_static: false,
+        is_virtual: false,
+        is_pure_virtual: false,
+        is_const: false,
+        is_variadic: false
+      })
+    });
+    assert!(cache.load(&opt, flags).is_none());
+    cache.store(&opt, &entry).unwrap();
+    assert_eq!(cache.load(&opt, flags).unwrap().model.decls, entry.model.decls);
+    assert!(cache.load(&opt, hash_flags(&["-std=c++17".to_string()])).is_none());
+
+    std::fs::write(&header, "int foo();\nint bar();\n").unwrap();
+    assert!(cache.load(&opt, flags).is_none());
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_stable_hasher()
+  {
+    assert_eq!(StableHasher::default().write(b"").finish(), 0xcbf29ce484222325);
+    assert_eq!(StableHasher::default().write(b"a").finish(), 0xaf63dc4c8601ec8c);
+    assert_eq!(StableHasher::default().write(b"foobar").finish(), 0x85944171f73967e8);
+    assert_ne!(hash_flags(&["ab".to_string(), "c".to_string()]), hash_flags(&["a".to_string(), "bc".to_string()]));
+  }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::parser::opts::CompileOption;
+
+// files changed since rev in working tree of the repository containing dir, including untracked ones
+pub fn changed_files(rev: &str, dir: &Path) -> anyhow::Result<HashSet<PathBuf>>
+{
+  let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"].map(OsStr::new))?.trim());
+  let diff = git(dir, &["diff", "--name-only", rev, "--"].map(OsStr::new))?;
+  let untracked = git(dir, &[OsStr::new("ls-files"), OsStr::new("--others"), OsStr::new("--exclude-standard"), OsStr::new("--full-name"), root.as_os_str()])?;
+  Ok(diff
+    .lines()
+    .chain(untracked.lines())
+    .filter(|line| !line.is_empty())
+    .map(|line| canonical(&root.join(line)))
+    .collect())
+}
+
+fn git(dir: &Path, args: &[&OsStr]) -> anyhow::Result<String>
+{
+  let output = Command::new("git")
+    .arg("-C")
+    .arg(dir)
+    .args(args)
+    .output()
+    .map_err(|e| anyhow::anyhow!("failed to run git: {}", e))?;
+  anyhow::ensure!(output.status.success(), "git {} failed: {}", args.join(OsStr::new(" ")).to_string_lossy(), String::from_utf8_lossy(&output.stderr).trim());
+  Ok(String::from_utf8(output.stdout)?)
+}
+
+pub fn canonical(path: &Path) -> PathBuf
+{
+  std::fs::canonicalize(path).unwrap_or(path.to_path_buf())
+}
+
+// source file followed by headers it transitively includes, found by scanning #include directives.
+// headers which can not be resolved against include paths of opt or extra include paths are ignored
+pub fn scan_includes(opt: &CompileOption, extra_includes: &[PathBuf]) -> Vec<PathBuf>
+{
+  let re = regex::Regex::new(r#"^\s*#\s*include\s*([<"])([^>"]+)[>"]"#).unwrap();
+  let mut files = vec![canonical(&opt.source)];
+  let mut seen = files.iter().cloned().collect::<HashSet<_>>();
+  let mut pending = files.clone();
+  while let Some(file) = pending.pop() {
+    let Ok(contents) = std::fs::read_to_string(&file) else { continue };
+    for cap in contents.lines().filter_map(|line| re.captures(line)) {
+      let local = match &cap[1] {
+        "\"" => file.parent().map(Path::to_path_buf),
+        _ => None
+      };
+      let found = local
+        .iter()
+        .chain(&opt.includes)
+        .chain(&opt.includes_system)
+        .chain(extra_includes)
+        .map(|dir| dir.join(&cap[2]))
+        .find(|path| path.is_file());
+      if let Some(path) = found.map(|path| canonical(&path)) {
+        if seen.insert(path.clone()) {
+          files.push(path.clone());
+          pending.push(path);
+        }
+      }
+    }
+  }
+  files
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_scan_includes()
+  {
+    let dir = std::env::temp_dir().join(format!("cxt-changes-test-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::create_dir_all(dir.join("include/floppy")).unwrap();
+    std::fs::create_dir_all(dir.join("extra")).unwrap();
+    std::fs::write(dir.join("src/main.cc"), "#include \"local.h\"\n#include <floppy/floppy.h>\n#include <vector>\n#include <extra.h>\n").unwrap();
+    std::fs::write(dir.join("extra/extra.h"), "#pragma once\n").unwrap();
+    std::fs::write(dir.join("src/local.h"), "#pragma once\n").unwrap();
+    std::fs::write(dir.join("include/floppy/floppy.h"), "#pragma once\n#include \"detail.h\"\n").unwrap();
+    std::fs::write(dir.join("include/floppy/detail.h"), "#pragma once\n#include <floppy/floppy.h>\n").unwrap();
+
+    let opt = CompileOption {
+      source: dir.join("src/main.cc"),
+      includes: vec![dir.join("include")],
+      ..Default::default()
+    };
+    assert_eq!(scan_includes(&opt, &[]).len(), 4);
+    let files = scan_includes(&opt, &[dir.join("extra")]);
+    let dir = canonical(&dir);
+    assert_eq!(files.len(), 5);
+    assert!(files.contains(&dir.join("extra/extra.h")));
+    assert_eq!(files[0], dir.join("src/main.cc"));
+    assert!(files.contains(&dir.join("src/local.h")));
+    assert!(files.contains(&dir.join("include/floppy/floppy.h")));
+    assert!(files.contains(&dir.join("include/floppy/detail.h")));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
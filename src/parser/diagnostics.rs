@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+use colored::Colorize;
+use clang::diagnostic::Severity;
+use crate::core::output;
+use crate::parser::model::Location;
+use crate::parser::opts::CompileOption;
+use crate::pb_print;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DiagnosticRecord
+{
+  pub severity: String,
+  pub message: String,
+  pub location: Location,
+  pub translation_unit: PathBuf
+}
+
+// compiler diagnostics of a parsed translation unit. text output shows errors only,
+// structured formats also carry warnings
+pub fn report(opt: &CompileOption, tu: &clang::TranslationUnit)
+{
+  let threshold = match output::is_text() {
+    true => Severity::Error,
+    false => Severity::Warning
+  };
+  for diagnostic in tu.get_diagnostics().iter().filter(|d| d.get_severity() >= threshold) {
+    let location = diagnostic.get_location().get_file_location();
+    let record = DiagnosticRecord {
+      severity: format!("{:?}", diagnostic.get_severity()).to_lowercase(),
+      message: diagnostic.get_text(),
+      location: Location { file: location.file.map(|f| f.get_path()), line: location.line, column: location.column },
+      translation_unit: opt.source.clone()
+    };
+    match output::is_text() {
+      true => pb_print!("{}:{}:{}: {} {}",
+        record.location.file.as_ref().map(|f| f.display().to_string()).unwrap_or("unknown".to_string()).bold().magenta(),
+        record.location.line,
+        record.location.column,
+        format!("{}:", record.severity).bold().red(),
+        record.message
+      ),
+      false => output::emit("diagnostic", &record)
+    }
+  }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -16,3 +17,10 @@ pub struct CMakeCompileCommand
   pub output: PathBuf
 }
 
+// editor buffers which override file contents on disk, path -> contents
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct UnsavedFiles
+{
+  pub files: HashMap<PathBuf, String>
+}
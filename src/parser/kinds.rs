@@ -0,0 +1,135 @@
+use clang::EntityKind as K;
+
+// every entity kind known to the clang crate, used to look kinds up by name
+const ALL_KINDS: [K; 241] = [
+  K::UnexposedDecl, K::StructDecl, K::UnionDecl, K::ClassDecl, K::EnumDecl, K::FieldDecl, K::EnumConstantDecl,
+  K::FunctionDecl, K::VarDecl, K::ParmDecl, K::ObjCInterfaceDecl, K::ObjCCategoryDecl, K::ObjCProtocolDecl,
+  K::ObjCPropertyDecl, K::ObjCIvarDecl, K::ObjCInstanceMethodDecl, K::ObjCClassMethodDecl, K::ObjCImplementationDecl,
+  K::ObjCCategoryImplDecl, K::TypedefDecl, K::Method, K::Namespace, K::LinkageSpec, K::Constructor, K::Destructor,
+  K::ConversionFunction, K::TemplateTypeParameter, K::NonTypeTemplateParameter, K::TemplateTemplateParameter,
+  K::FunctionTemplate, K::ClassTemplate, K::ClassTemplatePartialSpecialization, K::NamespaceAlias, K::UsingDirective,
+  K::UsingDeclaration, K::TypeAliasDecl, K::ObjCSynthesizeDecl, K::ObjCDynamicDecl, K::AccessSpecifier,
+  K::ObjCSuperClassRef, K::ObjCProtocolRef, K::ObjCClassRef, K::TypeRef, K::BaseSpecifier, K::TemplateRef,
+  K::NamespaceRef, K::MemberRef, K::LabelRef, K::OverloadedDeclRef, K::VariableRef, K::InvalidFile, K::InvalidDecl,
+  K::NotImplemented, K::InvalidCode, K::UnexposedExpr, K::DeclRefExpr, K::MemberRefExpr, K::CallExpr,
+  K::ObjCMessageExpr, K::BlockExpr, K::IntegerLiteral, K::FloatingLiteral, K::ImaginaryLiteral, K::StringLiteral,
+  K::CharacterLiteral, K::ParenExpr, K::UnaryOperator, K::ArraySubscriptExpr, K::BinaryOperator,
+  K::CompoundAssignOperator, K::ConditionalOperator, K::CStyleCastExpr, K::CompoundLiteralExpr, K::InitListExpr,
+  K::AddrLabelExpr, K::StmtExpr, K::GenericSelectionExpr, K::GNUNullExpr, K::StaticCastExpr, K::DynamicCastExpr,
+  K::ReinterpretCastExpr, K::ConstCastExpr, K::FunctionalCastExpr, K::TypeidExpr, K::BoolLiteralExpr,
+  K::NullPtrLiteralExpr, K::ThisExpr, K::ThrowExpr, K::NewExpr, K::DeleteExpr, K::UnaryExpr, K::ObjCStringLiteral,
+  K::ObjCEncodeExpr, K::ObjCSelectorExpr, K::ObjCProtocolExpr, K::ObjCBridgedCastExpr, K::PackExpansionExpr,
+  K::SizeOfPackExpr, K::LambdaExpr, K::ObjCBoolLiteralExpr, K::ObjCSelfExpr, K::OmpArraySectionExpr,
+  K::ObjCAvailabilityCheckExpr, K::FixedPointLiteral, K::UnexposedStmt, K::LabelStmt, K::CompoundStmt, K::CaseStmt,
+  K::DefaultStmt, K::IfStmt, K::SwitchStmt, K::WhileStmt, K::DoStmt, K::ForStmt, K::GotoStmt, K::IndirectGotoStmt,
+  K::ContinueStmt, K::BreakStmt, K::ReturnStmt, K::AsmStmt, K::ObjCAtTryStmt, K::ObjCAtCatchStmt,
+  K::ObjCAtFinallyStmt, K::ObjCAtThrowStmt, K::ObjCAtSynchronizedStmt, K::ObjCAutoreleasePoolStmt,
+  K::ObjCForCollectionStmt, K::CatchStmt, K::TryStmt, K::ForRangeStmt, K::SehTryStmt, K::SehExceptStmt,
+  K::SehFinallyStmt, K::SehLeaveStmt, K::MsAsmStmt, K::NullStmt, K::DeclStmt, K::OmpParallelDirective,
+  K::OmpSimdDirective, K::OmpForDirective, K::OmpSectionsDirective, K::OmpSectionDirective, K::OmpSingleDirective,
+  K::OmpParallelForDirective, K::OmpParallelSectionsDirective, K::OmpTaskDirective, K::OmpMasterDirective,
+  K::OmpCriticalDirective, K::OmpTaskyieldDirective, K::OmpBarrierDirective, K::OmpTaskwaitDirective,
+  K::OmpFlushDirective, K::OmpOrderedDirective, K::OmpAtomicDirective, K::OmpForSimdDirective,
+  K::OmpParallelForSimdDirective, K::OmpTargetDirective, K::OmpTeamsDirective, K::OmpTaskgroupDirective,
+  K::OmpCancellationPointDirective, K::OmpCancelDirective, K::OmpTargetDataDirective, K::OmpTaskLoopDirective,
+  K::OmpTaskLoopSimdDirective, K::OmpDistributeDirective, K::OmpTargetEnterDataDirective,
+  K::OmpTargetExitDataDirective, K::OmpTargetParallelDirective, K::OmpTargetParallelForDirective,
+  K::OmpTargetUpdateDirective, K::OmpDistributeParallelForDirective, K::OmpDistributeParallelForSimdDirective,
+  K::OmpDistributeSimdDirective, K::OmpTargetParallelForSimdDirective, K::OmpTargetSimdDirective,
+  K::OmpTeamsDistributeDirective, K::OmpTeamsDistributeSimdDirective, K::OmpTeamsDistributeParallelForSimdDirective,
+  K::OmpTeamsDistributeParallelForDirective, K::OmpTargetTeamsDirective, K::OmpTargetTeamsDistributeDirective,
+  K::OmpTargetTeamsDistributeParallelForDirective, K::OmpTargetTeamsDistributeParallelForSimdDirective,
+  K::OmpTargetTeamsDistributeSimdDirective, K::BitCastExpr, K::OmpMasterTaskLoopDirective,
+  K::OmpParallelMasterTaskLoopDirective, K::OmpMasterTaskLoopSimdDirective,
+  K::OmpParallelMasterTaskLoopSimdDirective, K::OmpParallelMasterDirective, K::TranslationUnit, K::UnexposedAttr,
+  K::IbActionAttr, K::IbOutletAttr, K::IbOutletCollectionAttr, K::FinalAttr, K::OverrideAttr, K::AnnotateAttr,
+  K::AsmLabelAttr, K::PackedAttr, K::PureAttr, K::ConstAttr, K::NoDuplicateAttr, K::CudaConstantAttr,
+  K::CudaDeviceAttr, K::CudaGlobalAttr, K::CudaHostAttr, K::CudaSharedAttr, K::VisibilityAttr, K::DllExport,
+  K::DllImport, K::NSReturnsRetained, K::NSReturnsNotRetained, K::NSReturnsAutoreleased, K::NSConsumesSelf,
+  K::NSConsumed, K::ObjCException, K::ObjCNSObject, K::ObjCIndependentClass, K::ObjCPreciseLifetime,
+  K::ObjCReturnsInnerPointer, K::ObjCRequiresSuper, K::ObjCRootClass, K::ObjCSubclassingRestricted,
+  K::ObjCExplicitProtocolImpl, K::ObjCDesignatedInitializer, K::ObjCRuntimeVisible, K::ObjCBoxable, K::FlagEnum,
+  K::WarnUnusedAttr, K::WarnUnusedResultAttr, K::AlignedAttr, K::PreprocessingDirective, K::MacroDefinition,
+  K::MacroExpansion, K::InclusionDirective, K::ModuleImportDecl, K::TypeAliasTemplateDecl, K::StaticAssert,
+  K::FriendDecl, K::OverloadCandidate
+];
+
+const FUNCTIONS: [K; 6] = [K::FunctionDecl, K::Method, K::Constructor, K::Destructor, K::ConversionFunction, K::FunctionTemplate];
+const TYPES: [K; 9] = [
+  K::StructDecl, K::UnionDecl, K::ClassDecl, K::EnumDecl, K::TypedefDecl, K::TypeAliasDecl,
+  K::TypeAliasTemplateDecl, K::ClassTemplate, K::ClassTemplatePartialSpecialization
+];
+const TEMPLATES: [K; 7] = [
+  K::FunctionTemplate, K::ClassTemplate, K::ClassTemplatePartialSpecialization, K::TypeAliasTemplateDecl,
+  K::TemplateTypeParameter, K::NonTypeTemplateParameter, K::TemplateTemplateParameter
+];
+const VARIABLES: [K; 4] = [K::VarDecl, K::FieldDecl, K::ParmDecl, K::EnumConstantDecl];
+const NAMESPACES: [K; 4] = [K::Namespace, K::NamespaceAlias, K::UsingDirective, K::LinkageSpec];
+const PREPROCESSING: [K; 4] = [K::PreprocessingDirective, K::MacroDefinition, K::MacroExpansion, K::InclusionDirective];
+
+// `FunctionDecl`, `functiondecl` and `function_decl` all name the same kind
+fn normalize(name: &str) -> String
+{
+  name.chars().filter(|c| *c != '_' && *c != '-').collect::<String>().to_lowercase()
+}
+
+fn group(name: &str) -> Option<Vec<K>>
+{
+  let by_suffix = |suffixes: &[&str]| ALL_KINDS
+    .iter()
+    .filter(|k| suffixes.iter().any(|s| format!("{:?}", k).ends_with(s)))
+    .copied()
+    .collect::<Vec<_>>();
+  Some(match name {
+    "functions" => FUNCTIONS.to_vec(),
+    "types" => TYPES.to_vec(),
+    "templates" => TEMPLATES.to_vec(),
+    "variables" => VARIABLES.to_vec(),
+    "namespaces" => NAMESPACES.to_vec(),
+    "preprocessing" => PREPROCESSING.to_vec(),
+    "attributes" => by_suffix(&["Attr"]),
+    "statements" => by_suffix(&["Stmt"]),
+    "expressions" => by_suffix(&["Expr", "Literal", "Operator"]),
+    _ => return None
+  })
+}
+
+// accepts entity kind names and group names, case-insensitive
+pub fn parse_kinds(names: &[String]) -> anyhow::Result<Vec<K>>
+{
+  let mut kinds = vec![];
+  for name in names {
+    let name = normalize(name);
+    if let Some(group) = group(&name) {
+      kinds.extend(group);
+      continue;
+    }
+    match ALL_KINDS.iter().find(|k| normalize(&format!("{:?}", k)) == name) {
+      Some(kind) => kinds.push(*kind),
+      None => return Err(anyhow::anyhow!("unknown entity kind: {}", name))
+    }
+  }
+  kinds.sort_by_key(|k| *k as i32);
+  kinds.dedup();
+  Ok(kinds)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_parse_kinds()
+  {
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    assert_eq!(parse_kinds(&names(&["FunctionDecl"])).unwrap(), vec![K::FunctionDecl]);
+    assert_eq!(parse_kinds(&names(&["function_decl", "METHOD"])).unwrap(), vec![K::FunctionDecl, K::Method]);
+    assert_eq!(parse_kinds(&names(&["functions", "method"])).unwrap().len(), FUNCTIONS.len());
+    assert!(parse_kinds(&names(&["attributes"])).unwrap().contains(&K::AnnotateAttr));
+    assert!(parse_kinds(&names(&["statements"])).unwrap().contains(&K::CompoundStmt));
+    assert!(parse_kinds(&names(&["expressions"])).unwrap().contains(&K::IntegerLiteral));
+    assert!(parse_kinds(&names(&["Functions"])).is_ok());
+    assert!(parse_kinds(&names(&["function"])).is_err());
+  }
+}
@@ -0,0 +1,148 @@
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static LIBCLANG: OnceLock<Libclang> = OnceLock::new();
+static CURSOR_OFFSET: OnceLock<Option<usize>> = OnceLock::new();
+
+// libclang api which is not available in every version the bindings can be loaded with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature
+{
+  ScopedEnums,
+  KeepGoing,
+  SingleFileParse,
+  // cursor kinds newer than libclang 10, which entity bindings report as NotImplemented, are read through raw bindings
+  RawCursorKinds
+}
+
+// loaded libclang
+#[derive(Debug, Clone)]
+pub struct Libclang
+{
+  pub version: String,
+  pub major: Option<u32>,
+  pub path: Option<PathBuf>
+}
+
+impl Libclang
+{
+  // must be called while an instance of clang::Clang exists
+  pub fn detect() -> &'static Libclang
+  {
+    LIBCLANG.get_or_init(|| {
+      let version = clang::get_version();
+      Libclang { major: major_version(&version), version, path: library_path() }
+    })
+  }
+
+  pub fn supports(&self, feature: Feature) -> bool
+  {
+    match feature {
+      Feature::ScopedEnums => clang_sys::clang_EnumDecl_isScoped::is_loaded(),
+      Feature::KeepGoing | Feature::SingleFileParse => self.major.is_none_or(|major| major >= 5),
+      Feature::RawCursorKinds => clang_sys::clang_getCursorKindSpelling::is_loaded() && CURSOR_OFFSET.get().is_some_and(Option::is_some)
+    }
+  }
+}
+
+// whether loaded libclang supports feature. assumed true before it is detected
+pub fn supports(feature: Feature) -> bool
+{
+  LIBCLANG.get().is_none_or(|libclang| libclang.supports(feature))
+}
+
+// clang::Entity keeps its CXCursor private, next to a reference to its translation unit. the cursor is located once
+// on a translation unit entity, whose kind is known to the bindings, and confirmed by libclang before it is ever read
+pub fn locate_cursor(root: &clang::Entity)
+{
+  CURSOR_OFFSET.get_or_init(|| {
+    if size_of::<clang::Entity>() != size_of::<clang_sys::CXCursor>() + size_of::<usize>() { return None }
+    if root.get_kind() != clang::EntityKind::TranslationUnit { return None }
+    [0, size_of::<usize>()].into_iter().find(|offset| unsafe {
+      let cursor = read_cursor(root, *offset);
+      cursor.kind == clang_sys::CXCursor_TranslationUnit
+        && clang_sys::clang_equalCursors(cursor, clang_sys::clang_getTranslationUnitCursor(clang_sys::clang_Cursor_getTranslationUnit(cursor))) != 0
+    })
+  });
+}
+
+// safety: offset must be 0 or size of a reference, so that the cursor is within the entity
+unsafe fn read_cursor(entity: &clang::Entity, offset: usize) -> clang_sys::CXCursor
+{
+  std::ptr::read_unaligned((entity as *const clang::Entity as *const u8).add(offset) as *const clang_sys::CXCursor)
+}
+
+// name of entity kind. kinds unknown to entity bindings are spelled by loaded libclang, e.g. ConceptDecl or RequiresExpr
+pub fn kind_name(entity: &clang::Entity) -> String
+{
+  match entity.get_kind() {
+    clang::EntityKind::NotImplemented => raw_kind_name(entity).unwrap_or("NotImplemented".to_string()),
+    kind => format!("{:?}", kind)
+  }
+}
+
+fn raw_kind_name(entity: &clang::Entity) -> Option<String>
+{
+  if !supports(Feature::RawCursorKinds) { return None }
+  let offset = (*CURSOR_OFFSET.get()?)?;
+  unsafe {
+    let spelling = clang_sys::clang_getCursorKindSpelling(clang_sys::clang_getCursorKind(read_cursor(entity, offset)));
+    let text = clang_sys::clang_getCString(spelling);
+    let name = (!text.is_null()).then(|| CStr::from_ptr(text).to_string_lossy().into_owned());
+    clang_sys::clang_disposeString(spelling);
+    name
+  }
+}
+
+// makes clang::Clang::new load libclang from given path. only possible with the `runtime` feature
+pub fn select(path: Option<&str>) -> anyhow::Result<()>
+{
+  let Some(path) = path else { return Ok(()) };
+  anyhow::ensure!(cfg!(feature = "runtime"), "--libclang requires cxt built with the `runtime` feature");
+  anyhow::ensure!(Path::new(path).is_file(), "libclang not found: {}", path);
+  std::env::set_var("LIBCLANG_PATH", path);
+  Ok(())
+}
+
+#[cfg(feature = "runtime")]
+fn library_path() -> Option<PathBuf>
+{
+  clang_sys::get_library().map(|library| library.path().to_path_buf())
+}
+
+#[cfg(not(feature = "runtime"))]
+fn library_path() -> Option<PathBuf>
+{
+  None
+}
+
+fn major_version(version: &str) -> Option<u32>
+{
+  let re = regex::Regex::new(r"version (\d+)").unwrap();
+  re.captures(version)?[1].parse().ok()
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_major_version()
+  {
+    assert_eq!(major_version("clang version 17.0.6 (https://github.com/llvm/llvm-project 6009708b4367)"), Some(17));
+    assert_eq!(major_version("Apple clang version 15.0.0 (clang-1500.3.9.4)"), Some(15));
+    assert_eq!(major_version("Ubuntu clang version 10.0.0-4ubuntu1"), Some(10));
+    assert_eq!(major_version("unknown"), None);
+  }
+
+  #[test]
+  fn test_supports()
+  {
+    let libclang = |major| Libclang { version: String::new(), major, path: None };
+    assert!(!libclang(Some(17)).supports(Feature::RawCursorKinds));
+    assert!(!libclang(Some(4)).supports(Feature::KeepGoing));
+    assert!(libclang(None).supports(Feature::SingleFileParse));
+  }
+}
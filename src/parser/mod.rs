@@ -1,5 +1,22 @@
+#[allow(clippy::module_inception)]
 mod parser;
 mod json;
 mod opts;
+mod cache;
+mod visitor;
+mod model;
+mod scope;
+mod kinds;
+mod pch;
+mod changes;
+mod profile;
+mod libclang;
+mod toolchain;
+mod diagnostics;
 
-pub use parser::Parser;
\ No newline at end of file
+pub use parser::Parser;
+pub use kinds::parse_kinds;
+pub use libclang::{kind_name, select as select_libclang};
+pub use visitor::{VisitFlags, Visitor};
+pub use model::{qualified_name, signature, Attribute, Decl, DeclKind, Location, Model, Parameter};
+pub use opts::{CompileOption, CompileOptions};
\ No newline at end of file
@@ -0,0 +1,528 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::parser::libclang::{self, Feature};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Location
+{
+  pub file: Option<PathBuf>,
+  pub line: u32,
+  pub column: u32
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Parameter
+{
+  pub name: String,
+  pub type_name: String
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TemplateParameter
+{
+  pub name: String,
+  pub kind: String,
+  pub type_name: Option<String>
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Attribute
+{
+  pub kind: String,
+  pub spelling: Option<String>
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeclKind
+{
+  Namespace,
+  Class {
+    keyword: String,
+    bases: Vec<String>,
+    template_parameters: Vec<TemplateParameter>
+  },
+  Function {
+    return_type: String,
+    parameters: Vec<Parameter>,
+    template_parameters: Vec<TemplateParameter>,
+    is_static: bool,
+    is_virtual: bool,
+    is_pure_virtual: bool,
+    is_const: bool,
+    is_variadic: bool
+  },
+  Enum {
+    underlying_type: Option<String>,
+    is_scoped: bool
+  },
+  Enumerator {
+    value: Option<i64>
+  },
+  Typedef {
+    underlying_type: Option<String>
+  },
+  Variable {
+    type_name: String,
+    is_static: bool
+  }
+}
+
+// owned declaration, independent of libclang translation unit lifetimes
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Decl
+{
+  pub id: usize,
+  pub parent: Option<usize>,
+  pub children: Vec<usize>,
+  pub entity_kind: String,
+  pub name: String,
+  #[serde(default)] pub qualified_name: String,
+  #[serde(default)] pub signature: Option<String>,
+  pub usr: Option<String>,
+  pub location: Location,
+  pub definition: Option<Location>,
+  pub attributes: Vec<Attribute>,
+  pub comment: Option<String>,
+  #[serde(flatten)] pub kind: DeclKind
+}
+
+// arena of declarations. parents always precede their children
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Model
+{
+  pub decls: Vec<Decl>,
+  #[serde(skip)] by_usr: HashMap<String, usize>,
+  #[serde(skip)] by_key: HashMap<(String, Location), usize>
+}
+
+impl Location
+{
+  pub fn from_entity(entity: &clang::Entity) -> Self
+  {
+    match entity.get_location() {
+      Some(loc) => {
+        let loc = loc.get_file_location();
+        Location { file: loc.file.map(|f| f.get_path()), line: loc.line, column: loc.column }
+      },
+      None => Location { file: None, line: 0, column: 0 }
+    }
+  }
+}
+
+// identifies the same entity across translation units. none for entities without usr or outside of any file
+pub fn key_of(entity: &clang::Entity) -> Option<(String, Location)>
+{
+  let location = Location::from_entity(entity);
+  location.file.as_ref()?;
+  Some((entity.get_usr()?.0, location))
+}
+
+// names of semantic parents joined with `::`, with arguments of template specializations. anonymous scopes are left out
+pub fn qualified_name(entity: &clang::Entity) -> String
+{
+  let mut names = vec![entity.get_name().unwrap_or_default() + &template_arguments(entity).unwrap_or_default()];
+  let mut parent = entity.get_semantic_parent();
+  while let Some(scope) = parent {
+    if scope.get_kind() == clang::EntityKind::TranslationUnit { break }
+    if let Some(name) = scope.get_name() {
+      names.push(name + &template_arguments(&scope).unwrap_or_default());
+    }
+    parent = scope.get_semantic_parent();
+  }
+  names.reverse();
+  names.join("::")
+}
+
+// `<int, 3>` for template specializations, none for other entities
+pub fn template_arguments(entity: &clang::Entity) -> Option<String>
+{
+  entity.get_template()?;
+  let arguments = entity
+    .get_template_arguments()
+    .unwrap_or_default()
+    .iter()
+    .map(|argument| match argument {
+      clang::TemplateArgument::Type(ty) => Some(ty.get_display_name()),
+      clang::TemplateArgument::Integral(value, _) => Some(value.to_string()),
+      clang::TemplateArgument::Nullptr => Some("nullptr".to_string()),
+      _ => None
+    })
+    .collect::<Option<Vec<_>>>()
+    .filter(|arguments| !arguments.is_empty());
+  match arguments {
+    Some(arguments) => Some(format!("<{}>", arguments.join(", "))),
+    // class specializations and arguments libclang can't describe are spelled out in display name
+    None => entity.get_display_name().and_then(|name| name.find('<').map(|i| name[i..].to_string()))
+  }
+}
+
+// full declaration of a function, e.g. `template <typename T> static bool floppy::Bar::foo(int, T &&) const & noexcept`.
+// none for other entities
+pub fn signature(entity: &clang::Entity) -> Option<String>
+{
+  use clang::EntityKind as K;
+  let kind = entity.get_kind();
+  if !matches!(kind, K::FunctionDecl | K::Method | K::Constructor | K::Destructor | K::ConversionFunction | K::FunctionTemplate) {
+    return None;
+  }
+  let mut parameters = entity
+    .get_children()
+    .iter()
+    .filter(|c| c.get_kind() == K::ParmDecl)
+    .map(|c| type_name(c.get_type()))
+    .collect::<Vec<_>>();
+  if entity.is_variadic() {
+    parameters.push("...".to_string());
+  }
+  let function_type = entity.get_type();
+  let spelled_qualifiers = function_type
+    .map(|ty| ty.get_display_name())
+    .and_then(|name| name.rfind(')').map(|i| name[i + 1..].to_string()))
+    .unwrap_or_default();
+  let qualifiers = [
+    (entity.is_const_method(), " const"),
+    (spelled_qualifiers.contains("volatile"), " volatile"),
+    (function_type.and_then(|ty| ty.get_ref_qualifier()) == Some(clang::RefQualifier::LValue), " &"),
+    (function_type.and_then(|ty| ty.get_ref_qualifier()) == Some(clang::RefQualifier::RValue), " &&"),
+    (matches!(entity.get_exception_specification(), Some(
+      clang::ExceptionSpecification::BasicNoexcept | clang::ExceptionSpecification::ComputedNoexcept |
+      clang::ExceptionSpecification::DynamicNone | clang::ExceptionSpecification::NoThrow
+    )), " noexcept"),
+    (entity.is_pure_virtual_method(), " = 0")
+  ];
+  Some(format!("{}{}{}{}({}){}",
+    match kind {
+      K::FunctionTemplate => template_prefix(&template_parameters(entity)),
+      _ => String::new()
+    },
+    if entity.is_static_method() { "static " } else if entity.is_virtual_method() { "virtual " } else { "" },
+    match kind {
+      K::Constructor | K::Destructor | K::ConversionFunction => String::new(),
+      _ => type_name(entity.get_result_type()) + " "
+    },
+    qualified_name(entity),
+    parameters.join(", "),
+    qualifiers.iter().filter(|(set, _)| *set).map(|(_, qualifier)| *qualifier).collect::<String>()
+  ))
+}
+
+// `template <typename T, int N> ` for a list of template parameters
+pub fn template_prefix(parameters: &[TemplateParameter]) -> String
+{
+  let parameters = parameters
+    .iter()
+    .map(|parameter| match (parameter.kind.as_str(), &parameter.type_name) {
+      ("type", _) => format!("typename {}", parameter.name),
+      ("non_type", Some(type_name)) => format!("{} {}", type_name, parameter.name),
+      _ => format!("template <...> class {}", parameter.name)
+    })
+    .collect::<Vec<_>>();
+  format!("template <{}> ", parameters.join(", "))
+}
+
+fn template_parameters(entity: &clang::Entity) -> Vec<TemplateParameter>
+{
+  use clang::EntityKind as K;
+  entity
+    .get_children()
+    .iter()
+    .filter(|c| matches!(c.get_kind(), K::TemplateTypeParameter | K::NonTypeTemplateParameter | K::TemplateTemplateParameter))
+    .map(|c| TemplateParameter {
+      name: c.get_name().unwrap_or_default(),
+      kind: match c.get_kind() {
+        K::TemplateTypeParameter => "type",
+        K::NonTypeTemplateParameter => "non_type",
+        _ => "template"
+      }.to_string(),
+      type_name: match c.get_kind() {
+        K::NonTypeTemplateParameter => Some(type_name(c.get_type())),
+        _ => None
+      }
+    })
+    .collect()
+}
+
+fn type_name(ty: Option<clang::Type>) -> String
+{
+  ty.map(|t| t.get_display_name()).unwrap_or_default()
+}
+
+impl DeclKind
+{
+  pub fn from_entity(entity: &clang::Entity) -> Option<Self>
+  {
+    use clang::EntityKind as K;
+    let children = entity.get_children();
+    let template_parameters = || template_parameters(entity);
+    Some(match entity.get_kind() {
+      K::Namespace => DeclKind::Namespace,
+      K::ClassDecl | K::StructDecl | K::UnionDecl | K::ClassTemplate | K::ClassTemplatePartialSpecialization => DeclKind::Class {
+        keyword: match entity.get_template_kind().unwrap_or(entity.get_kind()) {
+          K::StructDecl => "struct",
+          K::UnionDecl => "union",
+          _ => "class"
+        }.to_string(),
+        bases: children
+          .iter()
+          .filter(|c| c.get_kind() == K::BaseSpecifier)
+          .map(|c| type_name(c.get_type()))
+          .collect(),
+        template_parameters: template_parameters()
+      },
+      K::FunctionDecl | K::Method | K::Constructor | K::Destructor | K::ConversionFunction | K::FunctionTemplate => DeclKind::Function {
+        return_type: type_name(entity.get_result_type()),
+        parameters: children
+          .iter()
+          .filter(|c| c.get_kind() == K::ParmDecl)
+          .map(|c| Parameter { name: c.get_name().unwrap_or_default(), type_name: type_name(c.get_type()) })
+          .collect(),
+        template_parameters: template_parameters(),
+        is_static: entity.is_static_method(),
+        is_virtual: entity.is_virtual_method(),
+        is_pure_virtual: entity.is_pure_virtual_method(),
+        is_const: entity.is_const_method(),
+        is_variadic: entity.is_variadic()
+      },
+      K::EnumDecl => DeclKind::Enum {
+        underlying_type: entity.get_enum_underlying_type().map(|t| t.get_display_name()),
+        is_scoped: libclang::supports(Feature::ScopedEnums) && entity.is_scoped()
+      },
+      K::EnumConstantDecl => DeclKind::Enumerator {
+        value: entity.get_enum_constant_value().map(|(value, _)| value)
+      },
+      K::TypedefDecl | K::TypeAliasDecl | K::TypeAliasTemplateDecl => DeclKind::Typedef {
+        underlying_type: entity.get_typedef_underlying_type().map(|t| t.get_display_name())
+      },
+      K::VarDecl | K::FieldDecl => DeclKind::Variable {
+        type_name: type_name(entity.get_type()),
+        is_static: entity.get_storage_class() == Some(clang::StorageClass::Static)
+      },
+      _ => return None
+    })
+  }
+}
+
+impl Decl
+{
+  // none for entities which are not declarations tracked by the model
+  pub fn from_entity(entity: &clang::Entity) -> Option<Self>
+  {
+    let kind = DeclKind::from_entity(entity)?;
+    let location = Location::from_entity(entity);
+    Some(Decl {
+      id: 0,
+      parent: None,
+      children: vec![],
+      entity_kind: libclang::kind_name(entity),
+      name: entity.get_name().unwrap_or_default(),
+      qualified_name: qualified_name(entity),
+      signature: signature(entity),
+      usr: entity.get_usr().map(|usr| usr.0),
+      definition: match entity.is_definition() {
+        true => Some(location.clone()),
+        false => None
+      },
+      location,
+      attributes: entity
+        .get_children()
+        .iter()
+        .filter(|c| c.is_attribute())
+        .map(|c| Attribute { kind: libclang::kind_name(c), spelling: c.get_display_name() })
+        .collect(),
+      comment: entity.get_comment(),
+      kind
+    })
+  }
+
+  pub fn key(&self) -> Option<(String, Location)>
+  {
+    self.location.file.as_ref()?;
+    Some((self.usr.clone()?, self.location.clone()))
+  }
+}
+
+impl Model
+{
+  pub fn push(&mut self, parent: Option<usize>, mut decl: Decl) -> usize
+  {
+    let id = self.decls.len();
+    decl.id = id;
+    decl.parent = parent;
+    decl.children.clear();
+    if let Some(usr) = &decl.usr {
+      self.by_usr.entry(usr.clone()).or_insert(id);
+    }
+    if let Some(key) = decl.key() {
+      self.by_key.insert(key, id);
+    }
+    if let Some(parent) = parent {
+      self.decls[parent].children.push(id);
+    }
+    self.decls.push(decl);
+    id
+  }
+
+  // qualified name computed from the entity, or names of the declaration and its parents joined with `::`
+  // for declarations which don't have it. anonymous scopes are left out
+  pub fn qualified_name(&self, id: usize) -> String
+  {
+    if !self.decls[id].qualified_name.is_empty() {
+      return self.decls[id].qualified_name.clone();
+    }
+    let mut names = self
+      .ancestors(id)
+      .map(|decl| decl.name.as_str())
+      .filter(|name| !name.is_empty())
+      .collect::<Vec<_>>();
+    if !self.decls[id].name.is_empty() {
+      names.insert(0, &self.decls[id].name);
+    }
+    names.reverse();
+    names.join("::")
+  }
+
+  // parents of the declaration from the closest one
+  pub fn ancestors(&self, id: usize) -> impl Iterator<Item = &Decl>
+  {
+    std::iter::successors(self.decls[id].parent, |id| self.decls[*id].parent).map(|id| &self.decls[id])
+  }
+
+  // namespaces are always merged by usr. with merge_definitions, so are all other declarations,
+  // which joins declaration and definition sites into one record
+  pub fn merge(&mut self, fragment: Model, merge_definitions: bool)
+  {
+    let mut mapping: Vec<usize> = Vec::with_capacity(fragment.decls.len());
+    for decl in fragment.decls {
+      let parent = decl.parent.map(|p| mapping[p]);
+      let existing = match &decl.usr {
+        Some(usr) if merge_definitions || decl.kind == DeclKind::Namespace => self.by_usr.get(usr).copied(),
+        _ => None
+      }.or_else(|| decl.key().and_then(|key| self.by_key.get(&key).copied()));
+      match existing {
+        Some(id) => {
+          let target = &mut self.decls[id];
+          if target.definition.is_none() && decl.definition.is_some() {
+            target.definition = decl.definition;
+            target.kind = decl.kind;
+            target.signature = decl.signature;
+          }
+          if target.comment.is_none() {
+            target.comment = decl.comment;
+          }
+          mapping.push(id);
+        },
+        None => mapping.push(self.push(parent, decl))
+      }
+    }
+  }
+}
+
+// declaration for tests, declared at the start of floppy.h without usr
+#[cfg(test)]
+impl Decl
+{
+  pub fn fixture(name: &str, entity_kind: &str, kind: DeclKind) -> Decl
+  {
+    Decl {
+      id: 0,
+      parent: None,
+      children: vec![],
+      entity_kind: entity_kind.to_string(),
+      name: name.to_string(),
+      qualified_name: String::new(),
+      signature: None,
+      usr: None,
+      location: Location { file: Some(PathBuf::from("floppy.h")), line: 1, column: 1 },
+      definition: None,
+      attributes: vec![],
+      comment: None,
+      kind
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn decl(name: &str, usr: &str, kind: DeclKind, line: u32, definition: bool) -> Decl
+  {
+    let location = Location { file: Some(PathBuf::from(format!("{}.h", name))), line, column: 1 };
+    Decl {
+      usr: Some(usr.to_string()),
+      definition: match definition {
+        true => Some(location.clone()),
+        false => None
+      },
+      location,
+      ..Decl::fixture(name, "FunctionDecl", kind)
+    }
+  }
+
+  fn function() -> DeclKind
+  {
+    DeclKind::Function {
+      return_type: "void".to_string(),
+      parameters: vec![],
+      template_parameters: vec![],
+      is_static: false,
+      is_virtual: false,
+      is_pure_virtual: false,
+      is_const: false,
+      is_variadic: false
+    }
+  }
+
+  #[test]
+  fn test_merge()
+  {
+    let mut first = Model::default();
+    let ns = first.push(None, decl("floppy", "c:@N@floppy", DeclKind::Namespace, 1, true));
+    first.push(Some(ns), decl("foo", "c:@N@floppy@F@foo#", function(), 2, false));
+
+    let mut second = Model::default();
+    let ns = second.push(None, decl("floppy", "c:@N@floppy", DeclKind::Namespace, 5, true));
+    second.push(Some(ns), decl("foo", "c:@N@floppy@F@foo#", function(), 6, true));
+
+    let mut model = Model::default();
+    model.merge(first.clone(), false);
+    model.merge(first.clone(), false);
+    assert_eq!(model.decls.len(), 2);
+    model.merge(second.clone(), false);
+    assert_eq!(model.decls.len(), 3);
+    assert_eq!(model.decls[0].children, vec![1, 2]);
+
+    let mut anonymous = decl("", "", function(), 3, false);
+    anonymous.usr = None;
+    assert_eq!(anonymous.key(), None);
+
+    let mut merged = Model::default();
+    merged.merge(first, true);
+    merged.merge(second, true);
+    assert_eq!(merged.decls.len(), 2);
+    assert_eq!(merged.decls[1].definition.as_ref().unwrap().line, 6);
+
+    assert_eq!(merged.qualified_name(1), "floppy::foo");
+    assert_eq!(merged.ancestors(1).map(|decl| decl.id).collect::<Vec<_>>(), vec![0]);
+
+    merged.decls[1].qualified_name = "floppy::foo<int>".to_string();
+    assert_eq!(merged.qualified_name(1), "floppy::foo<int>");
+
+    let json = serde_json::to_string(&merged).unwrap();
+    assert_eq!(serde_json::from_str::<Model>(&json).unwrap().decls, merged.decls);
+  }
+
+  #[test]
+  fn test_template_prefix()
+  {
+    let parameter = |name: &str, kind: &str, type_name: Option<&str>| TemplateParameter {
+      name: name.to_string(),
+      kind: kind.to_string(),
+      type_name: type_name.map(str::to_string)
+    };
+    assert_eq!(template_prefix(&[parameter("T", "type", None), parameter("N", "non_type", Some("int"))]), "template <typename T, int N> ");
+    assert_eq!(template_prefix(&[parameter("C", "template", None)]), "template <template <...> class C> ");
+  }
+}
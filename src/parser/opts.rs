@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 use colored::Colorize;
 use crate::parser::json::{CMakeCompileCommand, CMakeCompileCommands};
+use crate::{pb_print, pb_status};
 // flags
 bitflags::bitflags! {
   #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -31,15 +32,34 @@ bitflags::bitflags! {
   }
 }
 
-#[derive(Debug, Clone)]
+// libclang translation unit parse options
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct ParseFlags: u32
+  {
+    const SKIP_FUNCTION_BODIES          = 0b00000001;
+    const INCOMPLETE                    = 0b00000010;
+    const DETAILED_PREPROCESSING_RECORD = 0b00000100;
+    const KEEP_GOING                    = 0b00001000;
+    const SINGLE_FILE_PARSE             = 0b00010000;
+
+    // fastest mode which still yields all declarations
+    const FAST = Self::SKIP_FUNCTION_BODIES.bits()
+      | Self::INCOMPLETE.bits()
+      | Self::KEEP_GOING.bits();
+  }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct CompileOptions
 {
   pub options: Vec<CompileOption>
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct CompileOption
 {
+  pub compiler: String,
   pub pwd: PathBuf,
   pub definitions: Vec<(String, String)>,
   pub includes: Vec<PathBuf>,
@@ -51,10 +71,10 @@ pub struct CompileOption
   pub output: PathBuf
 }
 
-impl Default for CompileOptions { fn default() -> Self { CompileOptions { options: vec![] } } }
 impl Default for CompileOption {
   fn default() -> Self {
     CompileOption {
+      compiler: String::new(),
       pwd: PathBuf::new(),
       definitions: vec![],
       includes: vec![],
@@ -84,7 +104,7 @@ impl CompileOptions
       options.push(CompileOption::from(&command));
     }
 
-    println!("  ☑️ successfully parsed {} build options", options.len().to_string().bold().bright_blue());
+    pb_status!("☑️ successfully parsed {} build options", options.len().to_string().bold().bright_blue());
     Ok(Self { options })
   }
 
@@ -100,7 +120,7 @@ impl CompileOptions
     anyhow::ensure!(path.exists(), "file not found: {}", path.display());
     anyhow::ensure!(path.is_file(), "not a file: {}", path.display());
 
-    println!("  ☑️ parsing build options: {}", path.display().to_string().bold().cyan());
+    pb_status!("☑️ parsing build options: {}", path.display().to_string().bold().cyan());
     let contents = std::fs::read_to_string(path)?;
     Self::from_string(&contents)
   }
@@ -108,7 +128,7 @@ impl CompileOptions
   pub fn pretty_print(&self)
   {
     if self.options.is_empty() {
-      println!("{}", "❌ no build options found".to_string().bold().red());
+      pb_print!("{}", "❌ no build options found".to_string().bold().red());
       return;
     }
     println!("working directory: {}", self.options[0].pwd.display().to_string().bold().cyan());
@@ -165,6 +185,7 @@ impl From<&CMakeCompileCommand> for CompileOption
       .unwrap_or("c++20".to_string());
 
     CompileOption {
+      compiler: that.command.split_whitespace().next().unwrap_or_default().to_string(),
       pwd: that.directory.clone(),
       definitions,
       includes,
@@ -178,11 +199,34 @@ impl From<&CMakeCompileCommand> for CompileOption
   }
 }
 
+impl ParseFlags
+{
+  // accepts flag names in kebab-case and presets `full` and `fast`
+  pub fn from_names(names: &[String]) -> anyhow::Result<Self>
+  {
+    let mut flags = ParseFlags::empty();
+    for name in names {
+      flags |= match name.to_lowercase().replace('_', "-").as_str() {
+        "full" => ParseFlags::empty(),
+        "fast" => ParseFlags::FAST,
+        "skip-function-bodies" => ParseFlags::SKIP_FUNCTION_BODIES,
+        "incomplete" => ParseFlags::INCOMPLETE,
+        "detailed-preprocessing-record" => ParseFlags::DETAILED_PREPROCESSING_RECORD,
+        "keep-going" => ParseFlags::KEEP_GOING,
+        "single-file-parse" => ParseFlags::SINGLE_FILE_PARSE,
+        _ => return Err(anyhow::anyhow!("unknown parse flag: {}", name))
+      };
+    }
+    Ok(flags)
+  }
+}
+
 impl CompileOption
 {
   pub fn pretty_print(&self)
   {
     println!("\tsource: {}", self.source.display().to_string().bold().green());
+    println!("\tcompiler: {}", self.compiler.bold().cyan());
     println!("\tc++ standard: {}", self.standard.bold().magenta());
     println!("\tdefinitions: [");
     for def in &self.definitions {
@@ -230,13 +274,13 @@ impl CompileOption
     if flags.contains(CompileOptionFlags::INCLUDES) && !self.includes.is_empty() {
       for inc in &self.includes {
         args.push("-I".to_string());
-        args.push(format!("{}", inc.display().to_string()));
+        args.push(inc.display().to_string());
       }
     }
     if flags.contains(CompileOptionFlags::INCLUDES_SYSTEM) && !self.includes_system.is_empty() {
       for inc in &self.includes_system {
         args.push("-isystem".to_string());
-        args.push(format!("{}", inc.display().to_string()));
+        args.push(inc.display().to_string());
       }
     }
     args
@@ -260,6 +304,7 @@ mod tests
     assert_eq!(got.source.display().to_string(), r#"D:\dev\my\floppy\src\detail\rtti.cc"#.to_string());
     assert_eq!(got.output.display().to_string(), r#"CMakeFiles\floppy.dir\src\detail\rtti.cc.obj"#.to_string());
     assert_eq!(got.pwd.display().to_string(), r#"D:/dev/my/floppy/build/Debug"#.to_string());
+    assert_eq!(got.compiler, r#"C:\msys64\mingw64\bin\c++.exe"#.to_string());
     assert_eq!(got.definitions.len(), 5);
     assert_eq!(got.definitions[0], ("CMAKE_PROJECT_VERSION_MAJOR".to_string(), "1".to_string()));
     assert_eq!(got.definitions[1], ("CMAKE_PROJECT_VERSION_MINOR".to_string(), "1".to_string()));
@@ -278,7 +323,7 @@ mod tests
     assert_eq!(got.warnings[0], "all".to_string());
     assert_eq!(got.warnings[1], "extra".to_string());
     assert_eq!(got.warnings[2], "pedantic".to_string());
-    assert_eq!(got.warnings_as_errors, true);
+    assert!(got.warnings_as_errors);
 
     assert_eq!(got.as_argument_array(CompileOptionFlags::ALL), [
       "-x", "c++", "-g",
@@ -327,8 +372,8 @@ mod tests
       | CompileOptionFlags::STANDARD
     ), got.as_argument_array(
       CompileOptionFlags::ALL
-      &! CompileOptionFlags::WARNINGS
-      &! CompileOptionFlags::WARNINGS_AS_ERRORS
+      & !CompileOptionFlags::WARNINGS
+      & !CompileOptionFlags::WARNINGS_AS_ERRORS
     ));
     assert_eq!(got.as_argument_array(
       CompileOptionFlags::INCLUDES
@@ -338,4 +383,17 @@ mod tests
     ), got.as_argument_array(CompileOptionFlags::REQUIRED_FOR_INDEXING)
     );
   }
+
+  #[test]
+  fn test_parse_flags()
+  {
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    assert_eq!(ParseFlags::from_names(&names(&["full"])).unwrap(), ParseFlags::empty());
+    assert_eq!(ParseFlags::from_names(&names(&["fast"])).unwrap(), ParseFlags::FAST);
+    assert_eq!(
+      ParseFlags::from_names(&names(&["skip-function-bodies", "KEEP_GOING"])).unwrap(),
+      ParseFlags::SKIP_FUNCTION_BODIES | ParseFlags::KEEP_GOING
+    );
+    assert!(ParseFlags::from_names(&names(&["fastest"])).is_err());
+  }
 }
\ No newline at end of file
@@ -1,116 +1,672 @@
-use std::path::Path;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use colored::Colorize;
 use crate::core::args::ProcessArgs;
-use crate::parser::opts::{CompileOption, CompileOptionFlags, CompileOptions};
-use crate::pb_print;
+use crate::core::output::{self, Progress};
+use crate::parser::cache::{hash_file, hash_flags, included_files, Cache, CacheEntry, CACHE_VERSION};
+use crate::parser::changes::{canonical, changed_files, scan_includes};
+use crate::parser::diagnostics;
+use crate::parser::json::UnsavedFiles;
+use crate::parser::libclang::{self, Feature, Libclang};
+use crate::parser::model::{key_of, Decl, Location, Model};
+use crate::parser::opts::{CompileOption, CompileOptionFlags, CompileOptions, ParseFlags};
+use crate::parser::pch::PchPlan;
+use crate::parser::profile::Profile;
+use crate::parser::scope::ProjectScope;
+use crate::parser::toolchain;
+use crate::parser::visitor::{VisitFlags, Visitor};
+use crate::{pb_debug, pb_print, pb_status};
 
-pub type FilterPredicate = fn(&clang::Entity) -> bool;
-
-pub struct Parser<'a>
+pub struct Parser
 {
   clang: Box<clang::Clang>,
   opts: CompileOptions,
   ignore_kind: Option<Vec<clang::EntityKind>>,
-  stored_entities: Vec<clang::Entity<'a>>,
+  only_kind: Option<Vec<clang::EntityKind>>,
+  parse_flags: ParseFlags,
+  pch_dir: Option<PathBuf>,
+  cache: Option<Cache>,
+  persist_ast: bool,
+  unsaved: Vec<clang::Unsaved>,
+  resource_dir: Option<PathBuf>,
+  system_includes: HashMap<String, Vec<PathBuf>>,
+  scope: ProjectScope,
+  model: Model,
+  units: HashMap<PathBuf, Unit>,
+  profile: Profile,
+  seen: HashSet<(String, Location)>,
+  merge_definitions: bool
+}
+
+// results of a single translation unit, kept to rebuild the model when only some of them are parsed again.
+// model holds every declaration of the unit, including those already visited through other units
+struct Unit
+{
+  model: Model,
+  files: Vec<PathBuf>,
+  keys: Vec<(String, Location)>
 }
 
-impl Parser<'_>
+struct Traversal<'p>
 {
-  pub fn new(args: &ProcessArgs, verbose: bool, ignore_kind: Option<Vec<clang::EntityKind>>) -> anyhow::Result<Self>
+  ignore_kind: &'p Option<Vec<clang::EntityKind>>,
+  only_kind: &'p Option<Vec<clang::EntityKind>>,
+  scope: &'p ProjectScope,
+  seen: &'p HashSet<(String, Location)>,
+  visitor: &'p mut dyn Visitor,
+  model: Model,
+  keys: HashSet<(String, Location)>,
+  count: usize
+}
+
+impl Parser
+{
+  pub fn new(
+    args: &ProcessArgs,
+    verbose: bool,
+    ignore_kind: Option<Vec<clang::EntityKind>>,
+    only_kind: Option<Vec<clang::EntityKind>>
+  ) -> anyhow::Result<Self>
   {
     let clang = match clang::Clang::new() {
       Ok(c) => Box::new(c),
       Err(e) => return Err(anyhow::anyhow!("failed to initialize clang: {}", e)),
     };
+    let libclang = Libclang::detect();
+    pb_status!("☑️ using {}{}",
+      libclang.version.bold().bright_blue(),
+      libclang.path.as_ref().map(|path| format!(" from {}", path.display())).unwrap_or_default()
+    );
+    let mut parse_flags = ParseFlags::from_names(&args.parse_flags)?;
+    for (flag, feature) in [(ParseFlags::KEEP_GOING, Feature::KeepGoing), (ParseFlags::SINGLE_FILE_PARSE, Feature::SingleFileParse)] {
+      if parse_flags.contains(flag) && !libclang.supports(feature) {
+        pb_print!("⚠️ {}", format!("{:?} is not supported by loaded libclang and is ignored", flag).yellow());
+        parse_flags.remove(flag);
+      }
+    }
+    anyhow::ensure!(!args.persist_ast || args.cache_dir.is_some(), "--persist-ast requires --cache-dir");
+    let unsaved = Self::unsaved_files(args)?;
+    let cache = match &args.cache_dir {
+      Some(_) if !unsaved.is_empty() => {
+        pb_print!("⚠️ {}", "cache is disabled while unsaved files are in use".to_string().yellow());
+        None
+      },
+      Some(dir) => Some(Cache::new(Path::new(dir.as_str()))?),
+      None => None
+    };
+    let opts = Self::load_options(args, cache.as_ref())?;
+    if verbose && output::messages_on_stdout() {
+      opts.pretty_print();
+    }
+    let resource_dir = toolchain::resource_dir(args.resource_dir.as_deref(), libclang)?;
+    match &resource_dir {
+      Some(dir) => pb_status!("☑️ using clang resource directory {}", dir.display().to_string().bold().cyan()),
+      None => pb_print!("⚠️ {}", "clang resource directory not found, builtin headers may be missing. see --resource-dir".yellow())
+    }
+    let mut system_includes = HashMap::new();
+    if !args.skip_system_probe {
+      for opt in &opts.options {
+        if system_includes.contains_key(&opt.compiler) { continue }
+        let dirs = toolchain::system_includes(&opt.compiler).unwrap_or_default();
+        pb_status!("☑️ found {} system include paths of {}", dirs.len().to_string().bold().bright_blue(), opt.compiler.bold().cyan());
+        system_includes.insert(opt.compiler.clone(), dirs);
+      }
+    }
+    // working directories of entries are build directories, unless sources are compiled in place, as with make or bear
+    let build_dirs = opts.options
+      .iter()
+      .filter(|opt| !opt.source.starts_with(&opt.pwd))
+      .map(|opt| opt.pwd.clone())
+      .collect::<HashSet<_>>();
+    let scope = ProjectScope::new(&args.scope, &build_dirs.into_iter().collect::<Vec<_>>());
+    Ok(Parser {
+      clang,
+      opts,
+      ignore_kind,
+      only_kind,
+      parse_flags,
+      pch_dir: match args.pch {
+        true => Some(args.cache_dir.as_ref().map(PathBuf::from).unwrap_or(std::env::temp_dir().join("cxt")).join("pch")),
+        false => None
+      },
+      cache,
+      persist_ast: args.persist_ast,
+      unsaved,
+      resource_dir,
+      system_includes,
+      scope,
+      model: Model::default(),
+      units: HashMap::new(),
+      profile: Profile::new(),
+      seen: HashSet::new(),
+      merge_definitions: args.merge_definitions
+    })
+  }
+
+  // compile database filtered by --ignore-tests and --changed-since
+  fn load_options(args: &ProcessArgs, cache: Option<&Cache>) -> anyhow::Result<CompileOptions>
+  {
     let mut opts = CompileOptions::from_path(Path::new(args.input.as_str()))?;
     if args.ignore_tests {
       let len = opts.options.len();
-      opts.options = opts
-        .options
-        .into_iter()
-        .filter(|opt| !opt.source.to_str().unwrap().contains("test"))
-        .collect();
-      println!("  ☑️ discarded {} test files ({} left)",
+      opts.options.retain(|opt| !opt.source.to_str().unwrap().contains("test"));
+      pb_status!("☑️ discarded {} test files ({} left)",
         (len - opts.options.len()).to_string().bold().yellow(),
         opts.options.len().to_string().bold().bright_blue()
       );
     }
-    if verbose {
-      opts.pretty_print();
+    if let Some(rev) = &args.changed_since {
+      // git runs in the first source root, or next to the first source file
+      let dir = match args.scope.source_root.as_ref().and_then(|roots| roots.first()) {
+        Some(root) => PathBuf::from(root),
+        None => opts.options.first().and_then(|opt| opt.source.parent()).map(Path::to_path_buf).unwrap_or(PathBuf::from("."))
+      };
+      let changed = changed_files(rev, &dir)?;
+      let include_flags = args.include_flags.iter().flatten().map(PathBuf::from).collect::<Vec<_>>();
+      let len = opts.options.len();
+      opts.options.retain(|opt| {
+        // includes recorded by a previous run are more precise than a scan, but may be missing
+        let files = match cache.and_then(|cache| cache.read(opt)) {
+          Some(entry) => entry.files.into_iter().map(|(path, _)| canonical(&path)).collect(),
+          None => scan_includes(opt, &include_flags)
+        };
+        files.iter().any(|file| changed.contains(file))
+      });
+      pb_status!("☑️ selected {} files affected by changes since {} ({} skipped)",
+        opts.options.len().to_string().bold().bright_blue(),
+        rev.bold().magenta(),
+        (len - opts.options.len()).to_string().bold().yellow()
+      );
+    }
+    Ok(opts)
+  }
+
+  // walks all translation units, feeds every found entity to visitor and returns the declaration model
+  pub fn parse(&mut self, args: &ProcessArgs, visitor: &mut dyn Visitor) -> anyhow::Result<&Model>
+  {
+    let options = self.opts.options.clone();
+    self.parse_options(args, visitor, &options)
+  }
+
+  // re-parses translation units affected by changes to their files or to the compile database, until interrupted.
+  // on_update receives the model after every round
+  pub fn watch(&mut self, args: &ProcessArgs, visitor: &mut dyn Visitor, on_update: &mut dyn FnMut(&Model) -> anyhow::Result<()>) -> anyhow::Result<()>
+  {
+    let input = Path::new(args.input.as_str());
+    let database = match input.is_dir() {
+      true => input.join("compile_commands.json"),
+      false => input.to_path_buf()
+    };
+    let mut snapshot = self.snapshot(&database);
+    pb_status!("👀 watching {} files for changes", snapshot.len().to_string().bold().bright_blue());
+    loop {
+      std::thread::sleep(Duration::from_millis(500));
+      let current = self.snapshot(&database);
+      let changed = current
+        .iter()
+        .filter(|(path, modified)| snapshot.get(*path) != Some(*modified))
+        .map(|(path, _)| path.clone())
+        .chain(snapshot.keys().filter(|path| !current.contains_key(*path)).cloned())
+        .collect::<HashSet<_>>();
+      if changed.is_empty() { continue }
+
+      let mut affected = vec![];
+      if changed.contains(&database) {
+        match Self::load_options(args, self.cache.as_ref()) {
+          Ok(opts) => {
+            affected = opts.options.iter().filter(|opt| !self.opts.options.contains(opt)).cloned().collect();
+            let sources = opts.options.iter().map(|opt| opt.source.clone()).collect::<HashSet<_>>();
+            self.units.retain(|source, _| sources.contains(source));
+            self.opts = opts;
+          },
+          Err(e) => pb_print!("⚠️ {}", format!("failed to reload compile database: {}", e).yellow())
+        }
+      }
+      for opt in &self.opts.options {
+        let touched = self.units
+          .get(&opt.source)
+          .is_none_or(|unit| unit.files.iter().any(|file| changed.contains(file)));
+        if touched && !affected.contains(opt) {
+          affected.push(opt.clone());
+        }
+      }
+      pb_status!("🔄 {} files changed, {} translation units affected",
+        changed.len().to_string().bold().bright_blue(),
+        affected.len().to_string().bold().bright_magenta()
+      );
+      match self.parse_options(args, visitor, &affected) {
+        Ok(model) => on_update(model)?,
+        Err(e) => pb_print!("❌ {}", e.to_string().red())
+      }
+      snapshot = self.snapshot(&database);
     }
-    Ok(Parser { clang, opts, ignore_kind, stored_entities: vec![] })
   }
 
-  // takes entity_fn and applies it to all found entities
-  pub fn parse(&mut self, args: &ProcessArgs, entity_fn: FilterPredicate) -> anyhow::Result<()>
+  // modification times of the compile database and of every file of known translation units
+  fn snapshot(&self, database: &Path) -> HashMap<PathBuf, SystemTime>
   {
-    let pb = indicatif::ProgressBar::new(self.opts.options.len() as u64)
+    std::iter::once(database)
+      .chain(self.opts.options.iter().map(|opt| opt.source.as_path()))
+      .chain(self.units.values().flat_map(|unit| unit.files.iter().map(PathBuf::as_path)))
+      .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok().map(|modified| (path.to_path_buf(), modified)))
+      .collect()
+  }
+
+  // parses given translation units and rebuilds the model from them and previously parsed units.
+  // entities found by units which are not parsed again stay visited
+  fn parse_options(&mut self, args: &ProcessArgs, visitor: &mut dyn Visitor, options: &[CompileOption]) -> anyhow::Result<&Model>
+  {
+    let sources = options.iter().map(|opt| &opt.source).collect::<HashSet<_>>();
+    self.seen = self.units
+      .iter()
+      .filter(|(source, _)| !sources.contains(source))
+      .flat_map(|(_, unit)| unit.keys.iter().cloned())
+      .collect();
+    let pb = indicatif::ProgressBar::new(options.len() as u64)
       .with_message("⌛ processing code")
       .with_style(
         indicatif::ProgressStyle::with_template("{spinner:.cyan} {wide_msg} {human_pos:2}/{human_len:2} ({percent:3}%) [{bar:40.yellow/yellow}] [{elapsed_precise}]")
           .unwrap()
           .progress_chars("█▒░")
       );
-    pb.set_draw_target(indicatif::ProgressDrawTarget::stdout_with_hz(30));
+    pb.set_draw_target(match (output::progress(), output::messages_on_stdout()) {
+      (Progress::Bar, true) => indicatif::ProgressDrawTarget::stdout_with_hz(30),
+      (Progress::Bar, false) => indicatif::ProgressDrawTarget::stderr_with_hz(30),
+      _ => indicatif::ProgressDrawTarget::hidden()
+    });
     pb.enable_steady_tick(Duration::from_millis(100));
-    for opt in &self.opts.options.clone() {
-      self.parse_entry(opt, args, &entity_fn)?;
+    visitor.begin_run(&self.opts)?;
+    self.profile = Profile::new();
+    let compiler_flags = options.iter().map(|opt| self.compiler_flags(opt, args)).collect::<Vec<_>>();
+    // saved translation units are visited in full, so cached declarations are not reused together with them.
+    // neither are they for visitors which need entities
+    let entries = options
+      .iter()
+      .zip(&compiler_flags)
+      .map(|(opt, compiler_flags)| self.cache
+        .as_ref()
+        .filter(|_| !self.persist_ast && visitor.accepts_cached())
+        .and_then(|cache| cache.load(opt, self.cache_key(compiler_flags)))
+      )
+      .collect::<Vec<_>>();
+    let pchs = self.build_pchs(options, &compiler_flags, &entries)?;
+    let mut reused = 0;
+    for ((opt, compiler_flags), entry) in options.iter().zip(compiler_flags).zip(entries) {
+      let flags = self.cache_key(&compiler_flags);
+      match entry {
+        Some(entry) => {
+          pb_status!("♻️ {} is up to date, reusing {} cached declarations",
+            opt.source.file_name().unwrap().to_os_string().into_string().unwrap().bold().cyan(),
+            entry.model.decls.len().to_string().bold().bright_green()
+          );
+          visitor.begin_translation_unit(opt)?;
+          replay(&entry.model, &self.seen, self.only_kind.as_deref(), visitor);
+          visitor.end_translation_unit(opt)?;
+          self.seen.extend(entry.keys.iter().cloned());
+          let files = entry.files.into_iter().map(|(path, _)| path).collect();
+          self.units.insert(opt.source.clone(), Unit { model: entry.model, files, keys: entry.keys });
+          reused += 1;
+        },
+        None => {
+          let mut compiler_flags = compiler_flags;
+          if let Some(pch) = pchs.get(&opt.source) {
+            compiler_flags.push("-include-pch".to_string());
+            compiler_flags.push(pch.display().to_string());
+          }
+          let unit = self.parse_entry(opt, &compiler_flags, flags, visitor)?;
+          if let Some(cache) = &self.cache {
+            let files = unit.files
+              .iter()
+              .map(|path| hash_file(path).map(|hash| (path.clone(), hash)))
+              .collect::<anyhow::Result<Vec<_>>>()?;
+            cache.store(opt, &CacheEntry { source: opt.source.clone(), flags, files, model: unit.model.clone(), keys: unit.keys.clone() })?;
+          }
+          self.units.insert(opt.source.clone(), unit);
+        }
+      }
       pb.inc(1);
+      if output::progress() == Progress::Plain {
+        pb_print!("[{}/{}] {}", pb.position(), options.len(), opt.source.display());
+      }
       pb.set_message(format!("⌛ processing {}", opt.source.file_name().unwrap().to_os_string().into_string().unwrap().bold().bright_magenta()));
     }
-    pb_print!("☑️ stored {} entities after processing all files", self.stored_entities.len().to_string().bold().green());
+    self.model = Model::default();
+    for opt in &self.opts.options {
+      if let Some(unit) = self.units.get(&opt.source) {
+        self.model.merge(unit.model.clone(), self.merge_definitions);
+      }
+    }
+    if self.cache.is_some() {
+      pb_status!("☑️ reused cached results for {} of {} files", reused.to_string().bold().green(), options.len().to_string().bold().bright_blue());
+    }
+    pb_status!("☑️ stored {} declarations after processing all files", self.model.decls.len().to_string().bold().green());
+    output::emit("summary", serde_json::json!({
+      "translation_units": options.len(),
+      "reused": reused,
+      "declarations": self.model.decls.len()
+    }));
+    if args.profile {
+      self.profile.print_slowest(10);
+      if self.pch_dir.is_some() {
+        self.print_pch_timing();
+      }
+    }
+    if let Some(path) = &args.profile_trace {
+      self.profile.write_trace(Path::new(path))?;
+    }
     pb.finish_with_message(format!("☑️ {}", String::from("processing completed!").bold().green()));
-    Ok(())
+    visitor.end_run()?;
+    Ok(&self.model)
   }
 
-  fn parse_entry(&mut self, opt: &CompileOption, args: &ProcessArgs, entity_fn: &FilterPredicate) -> anyhow::Result<()>
+  // measured effect of precompiled headers: average parse time of files parsed with and without them
+  fn print_pch_timing(&self)
   {
-    anyhow::ensure!(opt.source.exists(), "file not found: {}", opt.source.as_path().display());
-    anyhow::ensure!(opt.source.is_file(), "not a file: {}", opt.source.as_path().display());
+    let (with, without) = (self.profile.average_parse(true), self.profile.average_parse(false));
+    let millis = |parse: Option<Duration>| parse.map(|parse| format!("{} ms", parse.as_millis())).unwrap_or("n/a".to_string());
+    pb_print!("⏱️ average parse time {} with precompiled headers, {} without",
+      millis(with).bold().green(),
+      millis(without).bold().yellow()
+    );
+    output::emit("pch_timing", serde_json::json!({
+      "with_pch_ms": with.map(|parse| parse.as_millis()),
+      "without_pch_ms": without.map(|parse| parse.as_millis())
+    }));
+  }
 
-    let index = clang::Index::new(&self.clang, false, true);
+  // overlays from --unsaved json map and --stdin-file
+  fn unsaved_files(args: &ProcessArgs) -> anyhow::Result<Vec<clang::Unsaved>>
+  {
+    let mut overlays = match &args.unsaved {
+      Some(path) => {
+        let contents = std::fs::read_to_string(path)
+          .map_err(|e| anyhow::anyhow!("failed to read unsaved files from {}: {}", path, e))?;
+        serde_json::from_str::<UnsavedFiles>(&contents)?.files
+      },
+      None => HashMap::new()
+    };
+    if let Some(path) = &args.stdin_file {
+      overlays.insert(PathBuf::from(path), std::io::read_to_string(std::io::stdin())?);
+    }
+    Ok(overlays
+      .into_iter()
+      .map(|(path, contents)| clang::Unsaved::new(std::path::absolute(&path).unwrap_or(path), contents))
+      .collect())
+  }
+
+  // builds one precompiled header per group of translation units that need parsing and share flags and leading includes
+  fn build_pchs(&self, options: &[CompileOption], compiler_flags: &[Vec<String>], entries: &[Option<CacheEntry>]) -> anyhow::Result<HashMap<PathBuf, PathBuf>>
+  {
+    let mut pchs = HashMap::new();
+    let Some(dir) = &self.pch_dir else { return Ok(pchs) };
+    let units = options
+      .iter()
+      .zip(compiler_flags)
+      .zip(entries)
+      .filter(|(_, entry)| entry.is_none())
+      .map(|((opt, flags), _)| (opt.source.clone(), flags.clone()))
+      .collect::<Vec<_>>();
+    for plan in PchPlan::plan(&units) {
+      let start = Instant::now();
+      // files of a group whose header fails to build are parsed without it
+      let pch = match plan.build(&self.clang, dir, self.parse_flags, &self.unsaved) {
+        Ok(pch) => pch,
+        Err(e) => {
+          pb_print!("⚠️ {}", e.to_string().yellow());
+          continue;
+        }
+      };
+      pb_status!("☑️ built precompiled header of {} includes for {} files in {} ms",
+        plan.includes.len().to_string().bold().bright_blue(),
+        plan.sources.len().to_string().bold().bright_blue(),
+        start.elapsed().as_millis().to_string().bold().green()
+      );
+      output::emit("pch", serde_json::json!({
+        "includes": plan.includes.len(),
+        "sources": plan.sources.len(),
+        "build_ms": start.elapsed().as_millis()
+      }));
+      for source in plan.sources {
+        pchs.insert(source, pch.clone());
+      }
+    }
+    Ok(pchs)
+  }
+
+  // cached results depend on compiler flags, traversal settings and format of cache entries.
+  // visitor decisions are not part of it, since they don't change the model
+  fn cache_key(&self, compiler_flags: &[String]) -> u64
+  {
+    let mut key = vec![CACHE_VERSION.to_string()];
+    key.extend_from_slice(compiler_flags);
+    key.push(format!("{:?}", self.scope));
+    key.push(format!("{:?}", self.ignore_kind));
+    key.push(format!("{:?}", self.parse_flags));
+    hash_flags(&key)
+  }
+
+  // compile database entries selected for processing
+  pub fn options(&self) -> &CompileOptions
+  {
+    &self.opts
+  }
+
+  // arguments passed to libclang for a translation unit, except for a precompiled header
+  pub fn compiler_flags(&self, opt: &CompileOption, args: &ProcessArgs) -> Vec<String>
+  {
     let mut compiler_flags = opt.as_argument_array(CompileOptionFlags::REQUIRED_FOR_INDEXING);
-    if args.include_flags.is_some() {
-      let inc_flags = args.include_flags.clone().unwrap();
+    if let Some(inc_flags) = &args.include_flags {
       for flag in inc_flags {
         compiler_flags.push("-isystem".to_string());
-        compiler_flags.push(flag);
+        compiler_flags.push(flag.clone());
       }
     }
+    if let Some(dir) = &self.resource_dir {
+      compiler_flags.push("-resource-dir".to_string());
+      compiler_flags.push(dir.display().to_string());
+    }
+    for dir in self.system_includes.get(&opt.compiler).into_iter().flatten() {
+      compiler_flags.push("-isystem".to_string());
+      compiler_flags.push(dir.display().to_string());
+    }
+    compiler_flags
+  }
+
+  // returns declarations found in translation unit, all files it consists of and keys of all its entities with usr
+  fn parse_entry(&mut self, opt: &CompileOption, compiler_flags: &[String], flags: u64, visitor: &mut dyn Visitor) -> anyhow::Result<Unit>
+  {
+    anyhow::ensure!(opt.source.exists(), "file not found: {}", opt.source.as_path().display());
+    anyhow::ensure!(opt.source.is_file(), "not a file: {}", opt.source.as_path().display());
+
+    let start = self.profile.begin_unit();
+    let index = clang::Index::new(&self.clang, false, true);
+    let saved = match (&self.cache, self.persist_ast) {
+      (Some(cache), true) => cache.load_ast(&index, opt, flags),
+      _ => None
+    };
+    let tu = match saved {
+      Some(tu) => {
+        pb_status!("♻️ loaded saved translation unit for {}", opt.source.file_name().unwrap().to_os_string().into_string().unwrap().bold().cyan());
+        tu
+      },
+      None => self.parse_translation_unit(&index, opt, compiler_flags, flags)?
+    };
+    let parsed = start.elapsed();
+    pb_debug!("🔧 {} {}", opt.source.display().to_string().bold().cyan(), compiler_flags.join(" ").dimmed());
+    diagnostics::report(opt, &tu);
+    visitor.begin_translation_unit(opt)?;
+    let mut traversal = Traversal {
+      ignore_kind: &self.ignore_kind,
+      only_kind: &self.only_kind,
+      scope: &self.scope,
+      seen: &self.seen,
+      visitor,
+      model: Model::default(),
+      keys: HashSet::new(),
+      count: 0
+    };
+    libclang::locate_cursor(&tu.get_entity());
+    traversal.walk(tu.get_entity());
+    let pch = compiler_flags.iter().any(|flag| flag == "-include-pch");
+    self.profile.record(&opt.source, start, parsed, start.elapsed() - parsed, traversal.count, pch);
+    pb_status!("ℹ️ {} entities found in {}",
+      traversal.count.to_string().bold().bright_green(),
+      opt.source.file_name().unwrap().to_os_string().into_string().unwrap().bold().cyan()
+    );
+    let (model, keys) = (traversal.model, traversal.keys.into_iter().collect::<Vec<_>>());
+    self.seen.extend(keys.iter().cloned());
+    visitor.end_translation_unit(opt)?;
+    Ok(Unit { model, files: included_files(&tu, opt.source.as_path()), keys })
+  }
+
+  // saves parsed translation unit for later runs when persist_ast is set
+  fn parse_translation_unit<'i>(&self, index: &'i clang::Index<'i>, opt: &CompileOption, compiler_flags: &[String], flags: u64) -> anyhow::Result<clang::TranslationUnit<'i>>
+  {
     let tu = index
       .parser(opt.source.as_path())
-      .arguments(&compiler_flags)
+      .arguments(compiler_flags)
+      .unsaved(&self.unsaved)
+      .skip_function_bodies(self.parse_flags.contains(ParseFlags::SKIP_FUNCTION_BODIES))
+      .incomplete(self.parse_flags.contains(ParseFlags::INCOMPLETE))
+      .detailed_preprocessing_record(self.parse_flags.contains(ParseFlags::DETAILED_PREPROCESSING_RECORD))
+      .keep_going(self.parse_flags.contains(ParseFlags::KEEP_GOING))
+      .single_file_parse(self.parse_flags.contains(ParseFlags::SINGLE_FILE_PARSE))
       .parse()?;
-    let entities = self.recurse_entities_all(&tu)?;
-    pb_print!("ℹ️ {} entities found in {}",
-      entities.len().to_string().bold().bright_green(),
-      opt.source.file_name().unwrap().to_os_string().into_string().unwrap().bold().cyan()
-    );
-    for entity in entities {
-      match entity_fn(&entity) {
-        true => (),
-        false => ()
+    if let (Some(cache), true) = (&self.cache, self.persist_ast) {
+      if let Err(e) = cache.store_ast(&tu, opt, flags) {
+        pb_print!("⚠️ {}", e.to_string().yellow());
       }
     }
-    Ok(())
+    Ok(tu)
+  }
+}
+
+// feeds cached declarations to visitor. declarations visited through previous units are hidden together
+// with their children and those not matching only_kind are hidden alone, as in a walk
+fn replay(model: &Model, seen: &HashSet<(String, Location)>, only_kind: Option<&[clang::EntityKind]>, visitor: &mut dyn Visitor)
+{
+  let mut hidden = Vec::with_capacity(model.decls.len());
+  for decl in &model.decls {
+    let seen = decl.key().is_some_and(|key| seen.contains(&key));
+    hidden.push(seen || decl.parent.is_some_and(|parent| hidden[parent]));
+    let kind_matches = only_kind.is_none_or(|only_kind| only_kind.iter().any(|kind| format!("{:?}", kind) == decl.entity_kind));
+    if !hidden[decl.id] && kind_matches {
+      visitor.cached_decl(decl);
+    }
   }
+}
 
-  fn recurse_entities_all<'a>(&self, translation_unit: &'a clang::TranslationUnit) -> anyhow::Result<Vec<clang::Entity<'a>>>
+enum Frame<'tu>
+{
+  Enter { entity: clang::Entity<'tu>, parent: Option<clang::Entity<'tu>>, parent_decl: Option<usize>, in_scope: bool, visible: bool },
+  Leave(clang::Entity<'tu>)
+}
+
+impl Traversal<'_>
+{
+  // depth-first walk with an explicit stack, entities are streamed to visitor as soon as they are found.
+  // children of pruned entities are added to the model, but hidden from visitor.
+  // entities already visited in previous translation units are added to the model, but hidden from visitor
+  // together with their children. entities not matching only_kind are traversed and added to the model, but hidden from visitor.
+  // declarations inside of function bodies are not added to the model
+  fn walk(&mut self, root: clang::Entity)
   {
-    self.entity_children_all(translation_unit.get_entity())
+    let mut stack = vec![Frame::Enter { entity: root, parent: None, parent_decl: None, in_scope: true, visible: true }];
+    while let Some(frame) = stack.pop() {
+      let (entity, parent, parent_decl, in_scope, visible) = match frame {
+        Frame::Enter { entity, parent, parent_decl, in_scope, visible } => (entity, parent, parent_decl, in_scope, visible),
+        Frame::Leave(entity) => {
+          self.visitor.leave_entity(&entity);
+          continue;
+        }
+      };
+      let decl = match in_scope {
+        true => Decl::from_entity(&entity).map(|decl| self.model.push(parent_decl, decl)),
+        false => None
+      };
+      let visible = match key_of(&entity) {
+        Some(key) => {
+          let seen = self.seen.contains(&key);
+          self.keys.insert(key);
+          visible && !seen
+        },
+        None => visible
+      };
+      let flags = match self.only_kind {
+        _ if !visible => VisitFlags::empty(),
+        Some(only_kind) if !only_kind.contains(&entity.get_kind()) => VisitFlags::empty(),
+        _ => {
+          self.count += 1;
+          stack.push(Frame::Leave(entity));
+          self.visitor.enter_entity(&entity, parent.as_ref())
+        }
+      };
+      let visible = visible && !flags.contains(VisitFlags::PRUNE);
+      let in_scope = in_scope && !entity.is_statement() && !entity.is_expression();
+      // hidden entities are walked only to complete the model
+      if !visible && !in_scope { continue }
+
+      let mut children = vec![];
+      entity.visit_children(|child, _| {
+        if self.accepts(&child, flags) {
+          children.push(Frame::Enter { entity: child, parent: Some(entity), parent_decl: decl.or(parent_decl), in_scope, visible });
+        }
+        clang::EntityVisitResult::Continue
+      });
+      stack.extend(children.into_iter().rev());
+    }
   }
 
-  fn entity_children_all<'a>(&self, entity: clang::Entity<'a>) -> anyhow::Result<Vec<clang::Entity<'a>>>
+  fn accepts(&self, child: &clang::Entity, flags: VisitFlags) -> bool
   {
-    let mut entities = vec![entity.clone()];
-    for child in entity.get_children() {
-      if child.is_in_system_header() { continue }
-      if let Some(ignore_kind) = &self.ignore_kind {
-        if ignore_kind.contains(&child.get_kind()) { continue }
-      }
-      entities.append(&mut self.entity_children_all(child)?);
+    if child.is_in_system_header() || !self.scope.contains(child) { return false }
+    if flags.contains(VisitFlags::SKIP_BODY) && child.get_kind() == clang::EntityKind::CompoundStmt { return false }
+    if let Some(ignore_kind) = self.ignore_kind {
+      if ignore_kind.contains(&child.get_kind()) { return false }
     }
-    Ok(entities)
+    true
   }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+  use crate::parser::DeclKind;
+
+  #[derive(Default)]
+  struct Names(Vec<String>);
+
+  impl Visitor for Names
+  {
+    fn enter_entity(&mut self, _entity: &clang::Entity, _parent: Option<&clang::Entity>) -> VisitFlags
+    {
+      VisitFlags::empty()
+    }
+
+    fn cached_decl(&mut self, decl: &Decl)
+    {
+      self.0.push(decl.name.clone());
+    }
+  }
+
+  #[test]
+  fn test_replay()
+  {
+    let mut model = Model::default();
+    let ns = model.push(None, Decl::fixture("floppy", "Namespace", DeclKind::Namespace));
+    model.push(Some(ns), Decl::fixture("Point", "StructDecl", DeclKind::Class {
+      keyword: "struct".to_string(),
+      bases: vec![],
+      template_parameters: vec![]
+    }));
+    let replayed = |only_kind: Option<&[clang::EntityKind]>| {
+      let mut names = Names::default();
+      replay(&model, &HashSet::new(), only_kind, &mut names);
+      names.0
+    };
+    assert_eq!(replayed(None), vec!["floppy", "Point"]);
+    assert_eq!(replayed(Some(&[clang::EntityKind::StructDecl])), vec!["Point"]);
+  }
+}
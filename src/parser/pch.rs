@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+use crate::parser::cache::StableHasher;
+use crate::parser::opts::ParseFlags;
+
+// translation units with identical flags sharing the same leading includes
+#[derive(Debug, Clone)]
+pub struct PchPlan
+{
+  pub flags: Vec<String>,
+  pub includes: Vec<String>,
+  pub sources: Vec<PathBuf>
+}
+
+impl PchPlan
+{
+  // groups translation units by compiler flags. groups of a single file or without shared includes are dropped
+  pub fn plan(units: &[(PathBuf, Vec<String>)]) -> Vec<PchPlan>
+  {
+    let mut groups: Vec<PchPlan> = vec![];
+    let mut group_includes: Vec<Vec<Vec<String>>> = vec![];
+    for (source, flags) in units {
+      let includes = std::fs::read_to_string(source)
+        .map(|contents| leading_includes(&contents, source.parent().unwrap_or(Path::new(""))))
+        .unwrap_or_default();
+      match groups.iter().position(|group| &group.flags == flags) {
+        Some(i) => {
+          groups[i].sources.push(source.clone());
+          group_includes[i].push(includes);
+        },
+        None => {
+          groups.push(PchPlan { flags: flags.clone(), includes: vec![], sources: vec![source.clone()] });
+          group_includes.push(vec![includes]);
+        }
+      }
+    }
+    groups
+      .into_iter()
+      .zip(group_includes)
+      .filter(|(group, _)| group.sources.len() > 1)
+      .map(|(group, includes)| PchPlan { includes: common_prefix(&includes), ..group })
+      .filter(|plan| !plan.includes.is_empty())
+      .collect()
+  }
+
+  // writes a prelude header into dir and saves its precompiled form next to it
+  pub fn build(&self, clang: &clang::Clang, dir: &Path, parse_flags: ParseFlags, unsaved: &[clang::Unsaved]) -> anyhow::Result<PathBuf>
+  {
+    let hash = self.flags
+      .iter()
+      .chain(&self.includes)
+      .fold(StableHasher::default(), |mut hasher, field| *hasher.field(field.as_bytes()))
+      .finish();
+    let name = format!("{:016x}", hash);
+    let header = dir.join(format!("{}.hh", name));
+    let pch = dir.join(format!("{}.pch", name));
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&header, self.includes.join("\n") + "\n")?;
+
+    let mut flags = self.flags.clone();
+    if let Some(pos) = flags.iter().position(|f| f == "-x") {
+      flags[pos + 1] = "c++-header".to_string();
+    }
+    let index = clang::Index::new(clang, false, false);
+    let tu = index
+      .parser(header.as_path())
+      .arguments(&flags)
+      .unsaved(unsaved)
+      .incomplete(true)
+      .skip_function_bodies(parse_flags.contains(ParseFlags::SKIP_FUNCTION_BODIES))
+      .parse()
+      .map_err(|e| anyhow::anyhow!("failed to parse precompiled header {}: {}", header.display(), e))?;
+    tu.save(&pch)
+      .map_err(|e| anyhow::anyhow!("failed to save precompiled header {}: {}", pch.display(), e))?;
+    Ok(pch)
+  }
+}
+
+// `#include` directives from the start of the file, up to the first other directive or code, since macros and conditionals
+// may change what the following includes expand to. quoted includes found next to the file are made absolute,
+// because the prelude header lives elsewhere
+pub fn leading_includes(contents: &str, dir: &Path) -> Vec<String>
+{
+  let re = regex::Regex::new(r#"^#\s*include\s*(?:<([^>]+)>|"([^"]+)")"#).unwrap();
+  let mut includes = vec![];
+  let mut in_comment = false;
+  for line in contents.lines().map(str::trim) {
+    if in_comment {
+      in_comment = !line.contains("*/");
+      continue;
+    }
+    if line.is_empty() || line.starts_with("//") { continue }
+    if line.starts_with("/*") {
+      in_comment = !line.contains("*/");
+      continue;
+    }
+    let Some(cap) = re.captures(line) else { break };
+    match (cap.get(1), cap.get(2)) {
+      (Some(angled), _) => includes.push(format!("#include <{}>", angled.as_str())),
+      (_, Some(quoted)) => match dir.join(quoted.as_str()) {
+        local if local.is_file() => includes.push(format!("#include \"{}\"", std::fs::canonicalize(&local).unwrap_or(local).display())),
+        _ => includes.push(format!("#include \"{}\"", quoted.as_str()))
+      },
+      _ => break
+    }
+  }
+  includes
+}
+
+pub fn common_prefix(lists: &[Vec<String>]) -> Vec<String>
+{
+  let Some((first, rest)) = lists.split_first() else { return vec![] };
+  let len = rest
+    .iter()
+    .map(|list| first.iter().zip(list).take_while(|(a, b)| a == b).count())
+    .min()
+    .unwrap_or(first.len());
+  first[..len].to_vec()
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_leading_includes()
+  {
+    let contents = r#"
+      // copyright whs31
+      /*
+       * rtti support
+       */
+      #include <string>
+      #  include   <fmt/format.h>
+      #include "floppy/detail/rtti.h"
+      #include <vector>
+
+      namespace floppy { }
+      #include <map>
+    "#;
+    let dir = Path::new("/nonexistent");
+    assert_eq!(leading_includes(contents, dir), vec![
+      "#include <string>",
+      "#include <fmt/format.h>",
+      "#include \"floppy/detail/rtti.h\"",
+      "#include <vector>"
+    ]);
+    assert!(leading_includes("#define FMT_HEADER_ONLY\n#include <fmt/format.h>\n", dir).is_empty());
+    assert!(leading_includes("#ifdef _WIN32\n#include <windows.h>\n#endif\n", dir).is_empty());
+
+    let dir = std::env::temp_dir().join(format!("cxt-pch-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("core.h"), "#pragma once\n").unwrap();
+    let core = std::fs::canonicalize(dir.join("core.h")).unwrap();
+    assert_eq!(leading_includes("#include \"core.h\"\n", &dir), vec![format!("#include \"{}\"", core.display())]);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_common_prefix()
+  {
+    let list = |items: &[&str]| items.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+    assert_eq!(common_prefix(&[list(&["a", "b", "c"]), list(&["a", "b", "d"]), list(&["a", "b"])]), list(&["a", "b"]));
+    assert_eq!(common_prefix(&[list(&["a"]), list(&["b"])]), list(&[]));
+    assert_eq!(common_prefix(&[]), list(&[]));
+  }
+}
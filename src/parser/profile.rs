@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use colored::Colorize;
+use crate::pb_print;
+
+#[derive(Debug, Clone)]
+pub struct UnitProfile
+{
+  pub source: PathBuf,
+  pub start: Duration,
+  pub parse: Duration,
+  pub traversal: Duration,
+  pub entities: usize,
+  pub peak_memory: Option<u64>,
+  pub pch: bool
+}
+
+// timings of translation units parsed during one run, relative to its start
+#[derive(Debug, Clone)]
+pub struct Profile
+{
+  start: Instant,
+  pub units: Vec<UnitProfile>
+}
+
+#[derive(Debug, serde::Serialize)]
+struct TraceEvent
+{
+  name: String,
+  cat: &'static str,
+  ph: &'static str,
+  ts: u128,
+  dur: u128,
+  pid: u32,
+  tid: u32,
+  args: serde_json::Value
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace
+{
+  trace_events: Vec<TraceEvent>
+}
+
+impl Profile
+{
+  pub fn new() -> Self
+  {
+    Profile { start: Instant::now(), units: vec![] }
+  }
+
+  // starts measuring a translation unit. returns the moment it started
+  pub fn begin_unit(&self) -> Instant
+  {
+    reset_peak_memory();
+    Instant::now()
+  }
+
+  pub fn record(&mut self, source: &Path, start: Instant, parse: Duration, traversal: Duration, entities: usize, pch: bool)
+  {
+    self.units.push(UnitProfile {
+      source: source.to_path_buf(),
+      start: start.duration_since(self.start),
+      parse,
+      traversal,
+      entities,
+      peak_memory: peak_memory(),
+      pch
+    });
+  }
+
+  // average parse time of units parsed with or without a precompiled header, none if there are no such units
+  pub fn average_parse(&self, pch: bool) -> Option<Duration>
+  {
+    let parses = self.units.iter().filter(|unit| unit.pch == pch).map(|unit| unit.parse).collect::<Vec<_>>();
+    match parses.len() {
+      0 => None,
+      n => Some(parses.iter().sum::<Duration>() / n as u32)
+    }
+  }
+
+  pub fn slowest(&self, count: usize) -> Vec<&UnitProfile>
+  {
+    let mut units = self.units.iter().collect::<Vec<_>>();
+    units.sort_by_key(|unit| std::cmp::Reverse(unit.parse + unit.traversal));
+    units.truncate(count);
+    units
+  }
+
+  pub fn print_slowest(&self, count: usize)
+  {
+    pb_print!("⏱️ slowest translation units:");
+    for unit in self.slowest(count) {
+      pb_print!("   {} parse {} ms, traversal {} ms, {} entities, peak memory {}",
+        unit.source.file_name().unwrap().to_os_string().into_string().unwrap().bold().cyan(),
+        unit.parse.as_millis().to_string().bold().yellow(),
+        unit.traversal.as_millis().to_string().bold().yellow(),
+        unit.entities.to_string().bold().bright_green(),
+        unit.peak_memory
+          .map(|bytes| format!("{} MiB", bytes / (1024 * 1024)))
+          .unwrap_or("unknown".to_string())
+          .bold()
+          .magenta()
+      );
+    }
+  }
+
+  // chrome trace-event format, loadable by chrome://tracing and perfetto
+  pub fn write_trace(&self, path: &Path) -> anyhow::Result<()>
+  {
+    std::fs::write(path, serde_json::to_string(&self.trace())?)
+      .map_err(|e| anyhow::anyhow!("failed to write trace {}: {}", path.display(), e))
+  }
+
+  fn trace(&self) -> Trace
+  {
+    let mut events = vec![];
+    for unit in &self.units {
+      let args = serde_json::json!({
+        "source": unit.source,
+        "entities": unit.entities,
+        "peak_memory": unit.peak_memory,
+        "pch": unit.pch
+      });
+      let name = unit.source.file_name().unwrap_or_default().to_string_lossy().to_string();
+      events.push(TraceEvent {
+        name: name.clone(),
+        cat: "parse",
+        ph: "X",
+        ts: unit.start.as_micros(),
+        dur: unit.parse.as_micros(),
+        pid: std::process::id(),
+        tid: 1,
+        args: args.clone()
+      });
+      events.push(TraceEvent {
+        name,
+        cat: "traversal",
+        ph: "X",
+        ts: (unit.start + unit.parse).as_micros(),
+        dur: unit.traversal.as_micros(),
+        pid: std::process::id(),
+        tid: 1,
+        args
+      });
+    }
+    Trace { trace_events: events }
+  }
+}
+
+// peak resident set size is only tracked on linux, where it can be reset between translation units
+fn reset_peak_memory()
+{
+  #[cfg(target_os = "linux")]
+  let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+fn peak_memory() -> Option<u64>
+{
+  let status = std::fs::read_to_string("/proc/self/status").ok()?;
+  let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+  let kib = line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim().parse::<u64>().ok()?;
+  Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_profile()
+  {
+    let mut profile = Profile::new();
+    let start = profile.begin_unit();
+    profile.record(Path::new("/src/a.cc"), start, Duration::from_millis(10), Duration::from_millis(5), 100, true);
+    profile.record(Path::new("/src/b.cc"), start, Duration::from_millis(30), Duration::from_millis(1), 200, false);
+    profile.record(Path::new("/src/c.cc"), start, Duration::from_millis(1), Duration::from_millis(1), 300, false);
+    let slowest = profile.slowest(2).iter().map(|unit| unit.entities).collect::<Vec<_>>();
+    assert_eq!(slowest, vec![200, 100]);
+    assert_eq!(profile.average_parse(true), Some(Duration::from_millis(10)));
+    assert_eq!(profile.average_parse(false), Some(Duration::from_micros(15_500)));
+
+    let trace = serde_json::to_value(profile.trace()).unwrap();
+    let events = trace["traceEvents"].as_array().unwrap();
+    assert_eq!(events.len(), 6);
+    assert_eq!(events[0]["name"], "a.cc");
+    assert_eq!(events[0]["ph"], "X");
+    assert_eq!(events[1]["cat"], "traversal");
+    assert_eq!(events[1]["ts"].as_u64().unwrap(), events[0]["ts"].as_u64().unwrap() + 10_000);
+    assert_eq!(events[2]["args"]["entities"], 200);
+  }
+}
@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+use crate::core::args::ScopeArgs;
+
+// decides which files belong to the project. entities from other files are not processed
+#[derive(Debug, Clone, Default)]
+pub struct ProjectScope
+{
+  roots: Vec<String>,
+  build_dirs: Vec<String>,
+  third_party: Vec<String>,
+  include_build_dir: bool,
+  include_third_party: bool,
+  main_file_only: bool
+}
+
+impl ProjectScope
+{
+  // build directories of in-source builds contain source roots, so they can't tell build files apart and are dropped
+  pub fn new(args: &ScopeArgs, build_dirs: &[PathBuf]) -> Self
+  {
+    let normalize_all = |paths: &Option<Vec<String>>| paths
+      .iter()
+      .flatten()
+      .map(|p| normalize(&std::path::absolute(p).unwrap_or(PathBuf::from(p))))
+      .collect::<Vec<_>>();
+    let roots = normalize_all(&args.source_root);
+    ProjectScope {
+      build_dirs: build_dirs
+        .iter()
+        .map(|p| normalize(p))
+        .filter(|dir| !roots.iter().any(|root| is_under(root, dir)))
+        .collect(),
+      roots,
+      third_party: normalize_all(&args.third_party),
+      include_build_dir: args.include_build_dir,
+      include_third_party: args.include_third_party,
+      main_file_only: args.main_file_only
+    }
+  }
+
+  pub fn contains(&self, entity: &clang::Entity) -> bool
+  {
+    if self.main_file_only {
+      return entity.is_in_main_file();
+    }
+    match entity.get_location().and_then(|loc| loc.get_file_location().file) {
+      Some(file) => self.contains_path(&file.get_path()),
+      None => true
+    }
+  }
+
+  // build and third-party directories are checked first, because they are usually nested in source roots
+  pub fn contains_path(&self, path: &Path) -> bool
+  {
+    let path = normalize(path);
+    if self.build_dirs.iter().any(|dir| is_under(&path, dir)) {
+      return self.include_build_dir;
+    }
+    if self.third_party.iter().any(|dir| is_under(&path, dir)) {
+      return self.include_third_party;
+    }
+    self.roots.is_empty() || self.roots.iter().any(|root| is_under(&path, root))
+  }
+}
+
+// compile databases mix both kinds of separators, so paths are compared as strings with forward slashes
+fn normalize(path: &Path) -> String
+{
+  let path = path.to_string_lossy().replace('\\', "/").trim_end_matches('/').to_string();
+  match cfg!(windows) {
+    true => path.to_lowercase(),
+    false => path
+  }
+}
+
+fn is_under(path: &str, dir: &str) -> bool
+{
+  path == dir || path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_scope()
+  {
+    let args = ScopeArgs {
+      source_root: Some(vec!["/dev/my/floppy".to_string()]),
+      third_party: Some(vec!["/dev/my/floppy/thirdparty".to_string()]),
+      include_build_dir: false,
+      include_third_party: false,
+      main_file_only: false
+    };
+    let scope = ProjectScope::new(&args, &[PathBuf::from("/dev/my/floppy/build/Debug")]);
+    assert!(scope.contains_path(Path::new(r#"/dev/my/floppy\src\detail\rtti.cc"#)));
+    assert!(scope.contains_path(Path::new("/dev/my/floppy/include/floppy/floppy.h")));
+    assert!(!scope.contains_path(Path::new("/dev/my/floppy/build/Debug/floppy_export.h")));
+    assert!(!scope.contains_path(Path::new("/dev/my/floppy/thirdparty/fmt/format.h")));
+    assert!(!scope.contains_path(Path::new("/dev/my/floppy-ext/src/main.cc")));
+    assert!(!scope.contains_path(Path::new("/home/user/.conan2/p/fmtcdb79a57b9013/p/include/fmt/core.h")));
+
+    let scope = ProjectScope::new(&ScopeArgs { include_build_dir: true, include_third_party: true, ..args }, &[PathBuf::from("/dev/my/floppy/build/Debug")]);
+    assert!(scope.contains_path(Path::new("/dev/my/floppy/build/Debug/floppy_export.h")));
+    assert!(scope.contains_path(Path::new("/dev/my/floppy/thirdparty/fmt/format.h")));
+  }
+
+  #[test]
+  fn test_scope_in_source_build()
+  {
+    let args = ScopeArgs {
+      source_root: Some(vec!["/dev/my/floppy".to_string()]),
+      third_party: None,
+      include_build_dir: false,
+      include_third_party: false,
+      main_file_only: false
+    };
+    let scope = ProjectScope::new(&args, &[PathBuf::from("/dev/my/floppy")]);
+    assert!(scope.contains_path(Path::new("/dev/my/floppy/src/main.cc")));
+    assert!(!scope.contains_path(Path::new("/dev/my/other/main.cc")));
+  }
+}
@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use crate::parser::libclang::Libclang;
+
+// clang resource directory with builtin headers like <stddef.h>, matching loaded libclang version where possible.
+// explicit directory takes precedence and must contain them
+pub fn resource_dir(explicit: Option<&str>, libclang: &Libclang) -> anyhow::Result<Option<PathBuf>>
+{
+  if let Some(dir) = explicit {
+    anyhow::ensure!(has_builtins(Path::new(dir)), "not a clang resource directory: {}", dir);
+    return Ok(Some(PathBuf::from(dir)));
+  }
+  let mut libdirs = libclang.path
+    .iter()
+    .filter_map(|path| path.parent().map(Path::to_path_buf))
+    .collect::<Vec<_>>();
+  if let Some(major) = libclang.major {
+    libdirs.push(PathBuf::from(format!("/usr/lib/llvm-{}/lib", major)));
+  }
+  libdirs.extend(["/usr/lib", "/usr/lib64", "/usr/local/lib"].map(PathBuf::from));
+  Ok(libdirs
+    .iter()
+    .find_map(|dir| versioned_dir(&dir.join("clang"), libclang.major))
+    .or_else(|| command_output("clang", &["-print-resource-dir"]).map(|out| PathBuf::from(out.trim())))
+    .filter(|dir| has_builtins(dir)))
+}
+
+// `<...>` search list of the compiler used in compile database. none for compilers which are not gcc or clang compatible.
+// builtin header directories of the compiler are left out, as clang resource directory replaces them
+pub fn system_includes(compiler: &str) -> Option<Vec<PathBuf>>
+{
+  let name = Path::new(compiler).file_stem()?.to_string_lossy().to_lowercase();
+  if !["gcc", "g++", "clang", "c++", "cc"].iter().any(|known| name.contains(known)) { return None }
+  let output = Command::new(compiler)
+    .args(["-E", "-x", "c++", "-", "-v"])
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .output()
+    .ok()?;
+  Some(search_list(&String::from_utf8_lossy(&output.stderr))
+    .into_iter()
+    .filter(|dir| !is_builtin_dir(dir))
+    .collect())
+}
+
+fn search_list(output: &str) -> Vec<PathBuf>
+{
+  output
+    .lines()
+    .skip_while(|line| !line.starts_with("#include <...> search starts here:"))
+    .skip(1)
+    .take_while(|line| !line.starts_with("End of search list."))
+    .map(|line| PathBuf::from(line.trim().trim_end_matches("(framework directory)").trim()))
+    .collect()
+}
+
+fn is_builtin_dir(dir: &Path) -> bool
+{
+  let re = regex::Regex::new(r"[/\\]lib(64)?[/\\](gcc|clang)[/\\].*[/\\]include(-fixed)?$").unwrap();
+  re.is_match(&dir.to_string_lossy())
+}
+
+fn versioned_dir(dir: &Path, major: Option<u32>) -> Option<PathBuf>
+{
+  let mut entries = std::fs::read_dir(dir)
+    .ok()?
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| has_builtins(path))
+    .collect::<Vec<_>>();
+  entries.sort();
+  match major {
+    Some(major) => entries.into_iter().find(|path| {
+      let name = path.file_name().unwrap_or_default().to_string_lossy();
+      name == major.to_string() || name.starts_with(&format!("{}.", major))
+    }),
+    None => entries.pop()
+  }
+}
+
+fn has_builtins(dir: &Path) -> bool
+{
+  dir.join("include").join("stddef.h").is_file()
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String>
+{
+  let output = Command::new(program).args(args).stdin(Stdio::null()).output().ok()?;
+  match output.status.success() {
+    true => String::from_utf8(output.stdout).ok(),
+    false => None
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_search_list()
+  {
+    let output = r#"
+ignoring nonexistent directory "/usr/local/include/x86_64-linux-gnu"
+#include "..." search starts here:
+#include <...> search starts here:
+ /usr/include/c++/12
+ /usr/include/x86_64-linux-gnu/c++/12
+ /usr/lib/gcc/x86_64-linux-gnu/12/include
+ /usr/include
+ /System/Library/Frameworks (framework directory)
+End of search list.
+"#;
+    let dirs = search_list(output);
+    assert_eq!(dirs, [
+      "/usr/include/c++/12",
+      "/usr/include/x86_64-linux-gnu/c++/12",
+      "/usr/lib/gcc/x86_64-linux-gnu/12/include",
+      "/usr/include",
+      "/System/Library/Frameworks"
+    ].map(PathBuf::from));
+    assert!(is_builtin_dir(&dirs[2]));
+    assert!(is_builtin_dir(Path::new("/usr/lib/llvm-17/lib/clang/17/include")));
+    assert!(!is_builtin_dir(&dirs[0]));
+    assert!(!is_builtin_dir(&dirs[3]));
+  }
+
+  #[test]
+  fn test_versioned_dir()
+  {
+    let dir = std::env::temp_dir().join(format!("cxt-toolchain-test-{}", std::process::id()));
+    for version in ["10.0.0", "17"] {
+      std::fs::create_dir_all(dir.join(version).join("include")).unwrap();
+      std::fs::write(dir.join(version).join("include/stddef.h"), "").unwrap();
+    }
+    std::fs::create_dir_all(dir.join("18")).unwrap();
+    assert_eq!(versioned_dir(&dir, Some(10)), Some(dir.join("10.0.0")));
+    assert_eq!(versioned_dir(&dir, Some(17)), Some(dir.join("17")));
+    assert_eq!(versioned_dir(&dir, Some(18)), None);
+    assert_eq!(versioned_dir(&dir, None), Some(dir.join("17")));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,31 @@
+use crate::parser::model::Decl;
+use crate::parser::opts::{CompileOption, CompileOptions};
+
+bitflags::bitflags! {
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct VisitFlags: u32
+  {
+    // do not descend into children of this entity
+    const PRUNE     = 0b00000001;
+    // descend into children of this entity except for its body
+    const SKIP_BODY = 0b00000010;
+  }
+}
+
+// hooks are called in order: begin_run, { begin_translation_unit, { enter_entity, leave_entity } or { cached_decl }, end_translation_unit }, end_run
+pub trait Visitor
+{
+  fn begin_run(&mut self, _opts: &CompileOptions) -> anyhow::Result<()> { Ok(()) }
+  fn end_run(&mut self) -> anyhow::Result<()> { Ok(()) }
+
+  fn begin_translation_unit(&mut self, _opt: &CompileOption) -> anyhow::Result<()> { Ok(()) }
+  fn end_translation_unit(&mut self, _opt: &CompileOption) -> anyhow::Result<()> { Ok(()) }
+
+  fn enter_entity(&mut self, entity: &clang::Entity, parent: Option<&clang::Entity>) -> VisitFlags;
+  fn leave_entity(&mut self, _entity: &clang::Entity) {}
+
+  // whether declarations restored from cache are enough for this visitor. otherwise translation units are always parsed
+  fn accepts_cached(&self) -> bool { false }
+  // called instead of entity hooks for declarations restored from cache
+  fn cached_decl(&mut self, _decl: &Decl) {}
+}
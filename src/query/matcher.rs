@@ -0,0 +1,494 @@
+use clang::EntityKind as K;
+use crate::parser::{kind_name, qualified_name, Decl, DeclKind, Model};
+use crate::query::parse::{parse, Expr, Op};
+
+#[derive(Debug, Clone)]
+pub enum Text
+{
+  Exact(String),
+  Regex(regex::Regex)
+}
+
+// entity kinds matched by node matchers like `function(...)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind
+{
+  Decl,
+  Function,
+  Method,
+  Constructor,
+  Destructor,
+  Class,
+  Struct,
+  Union,
+  Enum,
+  Enumerator,
+  Namespace,
+  Variable,
+  Field,
+  Parameter,
+  Typedef,
+  Macro
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag
+{
+  Virtual,
+  Override,
+  Final,
+  Pure,
+  Static,
+  Const,
+  Inline,
+  Variadic,
+  Definition,
+  Template,
+  Public,
+  Protected,
+  Private
+}
+
+#[derive(Debug, Clone)]
+pub enum Matcher
+{
+  Node(NodeKind),
+  All(Vec<Matcher>),
+  Any(Vec<Matcher>),
+  Not(Box<Matcher>),
+  Name(Text),
+  QualifiedName(Text),
+  Type(Text),
+  Returns(Text),
+  InNamespace(Text),
+  InClass(Text),
+  Derives(Text),
+  HasParameter(Text),
+  File(Text),
+  HasAttribute(Text),
+  ParameterCount(usize),
+  Has(Box<Matcher>),
+  Flag(Flag)
+}
+
+const NODE_KINDS: [(&str, NodeKind); 16] = [
+  ("decl", NodeKind::Decl),
+  ("function", NodeKind::Function),
+  ("method", NodeKind::Method),
+  ("constructor", NodeKind::Constructor),
+  ("destructor", NodeKind::Destructor),
+  ("class", NodeKind::Class),
+  ("struct", NodeKind::Struct),
+  ("union", NodeKind::Union),
+  ("enum", NodeKind::Enum),
+  ("enumerator", NodeKind::Enumerator),
+  ("namespace", NodeKind::Namespace),
+  ("variable", NodeKind::Variable),
+  ("field", NodeKind::Field),
+  ("parameter", NodeKind::Parameter),
+  ("typedef", NodeKind::Typedef),
+  ("macro", NodeKind::Macro)
+];
+
+const FLAGS: [(&str, Flag); 13] = [
+  ("is_virtual", Flag::Virtual),
+  ("is_override", Flag::Override),
+  ("is_final", Flag::Final),
+  ("is_pure", Flag::Pure),
+  ("is_static", Flag::Static),
+  ("is_const", Flag::Const),
+  ("is_inline", Flag::Inline),
+  ("is_variadic", Flag::Variadic),
+  ("is_definition", Flag::Definition),
+  ("is_template", Flag::Template),
+  ("is_public", Flag::Public),
+  ("is_protected", Flag::Protected),
+  ("is_private", Flag::Private)
+];
+
+const TEXT_PREDICATES: [&str; 10] = [
+  "name", "qualified_name", "type", "returns", "in_namespace", "in_class", "derives", "has_parameter", "file", "has_attribute"
+];
+
+impl Text
+{
+  pub fn matches(&self, text: &str) -> bool
+  {
+    match self {
+      Text::Exact(value) => value == text,
+      Text::Regex(re) => re.is_match(text)
+    }
+  }
+}
+
+impl NodeKind
+{
+  pub fn matches(&self, entity: &clang::Entity) -> bool
+  {
+    let kind = entity.get_kind();
+    let in_record = || entity.get_semantic_parent().is_some_and(|parent| is_record(parent.get_kind()));
+    match self {
+      NodeKind::Decl => entity.is_declaration(),
+      NodeKind::Function => matches!(kind, K::FunctionDecl | K::FunctionTemplate) && !in_record(),
+      NodeKind::Method => matches!(kind, K::Method | K::Constructor | K::Destructor | K::ConversionFunction)
+        || (kind == K::FunctionTemplate && in_record()),
+      NodeKind::Constructor => kind == K::Constructor,
+      NodeKind::Destructor => kind == K::Destructor,
+      NodeKind::Class => is_record(kind),
+      NodeKind::Struct => kind == K::StructDecl || (kind == K::ClassTemplate && entity.get_template_kind() == Some(K::StructDecl)),
+      NodeKind::Union => kind == K::UnionDecl,
+      NodeKind::Enum => kind == K::EnumDecl,
+      NodeKind::Enumerator => kind == K::EnumConstantDecl,
+      NodeKind::Namespace => kind == K::Namespace,
+      NodeKind::Variable => kind == K::VarDecl,
+      NodeKind::Field => kind == K::FieldDecl,
+      NodeKind::Parameter => kind == K::ParmDecl,
+      NodeKind::Typedef => matches!(kind, K::TypedefDecl | K::TypeAliasDecl | K::TypeAliasTemplateDecl),
+      NodeKind::Macro => kind == K::MacroDefinition
+    }
+  }
+
+  // same as matches, for declarations of a model. parameters and macros are not part of it, so they are an error
+  pub fn matches_decl(&self, model: &Model, decl: &Decl) -> anyhow::Result<bool>
+  {
+    let kind = decl.entity_kind.as_str();
+    let in_record = || decl.parent.is_some_and(|parent| matches!(model.decls[parent].kind, DeclKind::Class { .. }));
+    let keyword = match &decl.kind {
+      DeclKind::Class { keyword, .. } => Some(keyword.as_str()),
+      _ => None
+    };
+    Ok(match self {
+      NodeKind::Decl => true,
+      NodeKind::Function => matches!(kind, "FunctionDecl" | "FunctionTemplate") && !in_record(),
+      NodeKind::Method => matches!(kind, "Method" | "Constructor" | "Destructor" | "ConversionFunction")
+        || (kind == "FunctionTemplate" && in_record()),
+      NodeKind::Constructor => kind == "Constructor",
+      NodeKind::Destructor => kind == "Destructor",
+      NodeKind::Class => keyword.is_some(),
+      NodeKind::Struct => keyword == Some("struct"),
+      NodeKind::Union => keyword == Some("union"),
+      NodeKind::Enum => kind == "EnumDecl",
+      NodeKind::Enumerator => kind == "EnumConstantDecl",
+      NodeKind::Namespace => kind == "Namespace",
+      NodeKind::Variable => kind == "VarDecl",
+      NodeKind::Field => kind == "FieldDecl",
+      NodeKind::Typedef => matches!(decl.kind, DeclKind::Typedef { .. }),
+      NodeKind::Parameter | NodeKind::Macro => {
+        let name = NODE_KINDS.iter().find(|(_, kind)| kind == self).map(|(name, _)| *name).unwrap_or_default();
+        return Err(unsupported(name));
+      }
+    })
+  }
+}
+
+impl Flag
+{
+  pub fn matches(&self, entity: &clang::Entity) -> bool
+  {
+    let has_child = |kind: K| entity.get_children().iter().any(|c| c.get_kind() == kind);
+    match self {
+      Flag::Virtual => entity.is_virtual_method(),
+      Flag::Override => has_child(K::OverrideAttr),
+      Flag::Final => has_child(K::FinalAttr),
+      Flag::Pure => entity.is_pure_virtual_method(),
+      Flag::Static => entity.is_static_method() || entity.get_storage_class() == Some(clang::StorageClass::Static),
+      Flag::Const => entity.is_const_method(),
+      Flag::Inline => entity.is_inline_function(),
+      Flag::Variadic => entity.is_variadic(),
+      Flag::Definition => entity.is_definition(),
+      Flag::Template => matches!(entity.get_kind(),
+        K::FunctionTemplate | K::ClassTemplate | K::ClassTemplatePartialSpecialization | K::TypeAliasTemplateDecl
+      ),
+      Flag::Public => entity.get_accessibility() == Some(clang::Accessibility::Public),
+      Flag::Protected => entity.get_accessibility() == Some(clang::Accessibility::Protected),
+      Flag::Private => entity.get_accessibility() == Some(clang::Accessibility::Private)
+    }
+  }
+
+  // same as matches, for declarations of a model. inline and access flags are not part of it, so they are an error
+  pub fn matches_decl(&self, decl: &Decl) -> anyhow::Result<bool>
+  {
+    let has_attribute = |kind: &str| decl.attributes.iter().any(|attr| attr.kind == kind);
+    Ok(match (self, &decl.kind) {
+      (Flag::Virtual, DeclKind::Function { is_virtual, .. }) => *is_virtual,
+      (Flag::Pure, DeclKind::Function { is_pure_virtual, .. }) => *is_pure_virtual,
+      (Flag::Static, DeclKind::Function { is_static, .. } | DeclKind::Variable { is_static, .. }) => *is_static,
+      (Flag::Const, DeclKind::Function { is_const, .. }) => *is_const,
+      (Flag::Variadic, DeclKind::Function { is_variadic, .. }) => *is_variadic,
+      (Flag::Override, _) => has_attribute("OverrideAttr"),
+      (Flag::Final, _) => has_attribute("FinalAttr"),
+      (Flag::Definition, _) => decl.definition.is_some(),
+      (Flag::Template, _) => matches!(decl.entity_kind.as_str(),
+        "FunctionTemplate" | "ClassTemplate" | "ClassTemplatePartialSpecialization" | "TypeAliasTemplateDecl"
+      ),
+      (Flag::Inline | Flag::Public | Flag::Protected | Flag::Private, _) => {
+        let name = FLAGS.iter().find(|(_, flag)| flag == self).map(|(name, _)| *name).unwrap_or_default();
+        return Err(unsupported(name));
+      },
+      _ => false
+    })
+  }
+}
+
+impl Matcher
+{
+  pub fn from_query(query: &str) -> anyhow::Result<Self>
+  {
+    Self::compile(&parse(query)?)
+  }
+
+  fn compile(expr: &Expr) -> anyhow::Result<Self>
+  {
+    match expr {
+      Expr::Not(expr) => Ok(Matcher::Not(Box::new(Self::compile(expr)?))),
+      Expr::Or(exprs) => Ok(Matcher::Any(exprs.iter().map(Self::compile).collect::<anyhow::Result<_>>()?)),
+      Expr::Field { name, op, value } => Self::text_predicate(name, match op {
+        Op::Eq => Text::Exact(value.clone()),
+        Op::Regex => Text::Regex(regex::Regex::new(value)?)
+      }),
+      Expr::Call { name, args } => {
+        let matchers = || args.iter().map(Self::compile).collect::<anyhow::Result<Vec<_>>>();
+        if let Some((_, kind)) = NODE_KINDS.iter().find(|(n, _)| n == name) {
+          let mut all = vec![Matcher::Node(*kind)];
+          all.extend(matchers()?);
+          return Ok(Matcher::All(all));
+        }
+        if let Some((_, flag)) = FLAGS.iter().find(|(n, _)| n == name) {
+          anyhow::ensure!(args.is_empty(), "{} takes no arguments", name);
+          return Ok(Matcher::Flag(*flag));
+        }
+        match (name.as_str(), args.as_slice()) {
+          ("all_of", _) => Ok(Matcher::All(matchers()?)),
+          ("any_of", _) => Ok(Matcher::Any(matchers()?)),
+          ("has", [arg]) => Ok(Matcher::Has(Box::new(Self::compile(arg)?))),
+          ("parameter_count", [Expr::Int(count)]) => Ok(Matcher::ParameterCount(usize::try_from(*count)?)),
+          (name, [Expr::Str(value)]) if TEXT_PREDICATES.contains(&name) => Self::text_predicate(name, Text::Exact(value.clone())),
+          (name, _) if TEXT_PREDICATES.contains(&name) => Err(anyhow::anyhow!("{} expects a single string argument", name)),
+          ("has" | "parameter_count", _) => Err(anyhow::anyhow!("invalid arguments of {}", name)),
+          _ => Err(anyhow::anyhow!("unknown matcher: {}", name))
+        }
+      },
+      Expr::Str(value) => Err(anyhow::anyhow!("expected matcher, found string \"{}\"", value)),
+      Expr::Int(value) => Err(anyhow::anyhow!("expected matcher, found number {}", value))
+    }
+  }
+
+  fn text_predicate(name: &str, text: Text) -> anyhow::Result<Self>
+  {
+    Ok(match name {
+      "name" => Matcher::Name(text),
+      "qualified_name" => Matcher::QualifiedName(text),
+      "type" => Matcher::Type(text),
+      "returns" => Matcher::Returns(text),
+      "in_namespace" => Matcher::InNamespace(text),
+      "in_class" => Matcher::InClass(text),
+      "derives" => Matcher::Derives(text),
+      "has_parameter" => Matcher::HasParameter(text),
+      "file" => Matcher::File(text),
+      "has_attribute" => Matcher::HasAttribute(text),
+      _ => return Err(anyhow::anyhow!("unknown field: {}", name))
+    })
+  }
+
+  pub fn matches(&self, entity: &clang::Entity) -> bool
+  {
+    let type_name = |ty: Option<clang::Type>| ty.map(|ty| ty.get_display_name());
+    match self {
+      Matcher::Node(kind) => kind.matches(entity),
+      Matcher::All(matchers) => matchers.iter().all(|m| m.matches(entity)),
+      Matcher::Any(matchers) => matchers.iter().any(|m| m.matches(entity)),
+      Matcher::Not(matcher) => !matcher.matches(entity),
+      Matcher::Name(text) => entity.get_name().is_some_and(|name| text.matches(&name)),
+      Matcher::QualifiedName(text) => entity.get_name().is_some() && text.matches(&qualified_name(entity)),
+      Matcher::Type(text) => type_name(entity.get_type()).is_some_and(|ty| text.matches(&ty)),
+      Matcher::Returns(text) => type_name(entity.get_result_type()).is_some_and(|ty| text.matches(&ty)),
+      Matcher::InNamespace(text) => ancestors(entity).any(|a| a.get_kind() == K::Namespace && names_match(&a, text)),
+      Matcher::InClass(text) => ancestors(entity).any(|a| is_record(a.get_kind()) && names_match(&a, text)),
+      Matcher::Derives(text) => derives(entity, text, 0),
+      Matcher::HasParameter(text) => entity
+        .get_arguments()
+        .unwrap_or_default()
+        .iter()
+        .any(|arg| type_name(arg.get_type()).is_some_and(|ty| text.matches(&ty))),
+      Matcher::File(text) => entity
+        .get_location()
+        .and_then(|loc| loc.get_file_location().file)
+        .is_some_and(|file| text.matches(&file.get_path().to_string_lossy().replace('\\', "/"))),
+      Matcher::HasAttribute(text) => entity
+        .get_children()
+        .iter()
+        .filter(|c| c.is_attribute())
+        .any(|c| c.get_display_name().is_some_and(|name| text.matches(&name)) || text.matches(&kind_name(c))),
+      Matcher::ParameterCount(count) => entity.get_arguments().is_some_and(|args| args.len() == *count),
+      Matcher::Has(matcher) => entity.get_children().iter().any(|c| matcher.matches(c)),
+      Matcher::Flag(flag) => flag.matches(entity)
+    }
+  }
+}
+
+impl Matcher
+{
+  // evaluates matcher on a declaration of the model instead of a live entity.
+  // fails for predicates which need information the model doesn't keep
+  pub fn matches_decl(&self, model: &Model, decl: &Decl) -> anyhow::Result<bool>
+  {
+    let names_match = |decl: &Decl, text: &Text| text.matches(&decl.name) || text.matches(&model.qualified_name(decl.id));
+    Ok(match self {
+      Matcher::Node(kind) => kind.matches_decl(model, decl)?,
+      Matcher::All(matchers) => matchers.iter().try_fold(true, |all, m| anyhow::Ok(all && m.matches_decl(model, decl)?))?,
+      Matcher::Any(matchers) => matchers.iter().try_fold(false, |any, m| anyhow::Ok(any || m.matches_decl(model, decl)?))?,
+      Matcher::Not(matcher) => !matcher.matches_decl(model, decl)?,
+      Matcher::Name(text) => text.matches(&decl.name),
+      Matcher::QualifiedName(text) => text.matches(&model.qualified_name(decl.id)),
+      Matcher::Type(text) => match &decl.kind {
+        DeclKind::Variable { type_name, .. } => text.matches(type_name),
+        DeclKind::Typedef { underlying_type: Some(ty) } | DeclKind::Enum { underlying_type: Some(ty), .. } => text.matches(ty),
+        _ => false
+      },
+      Matcher::Returns(text) => matches!(&decl.kind, DeclKind::Function { return_type, .. } if text.matches(return_type)),
+      Matcher::InNamespace(text) => model.ancestors(decl.id).any(|a| a.kind == DeclKind::Namespace && names_match(a, text)),
+      Matcher::InClass(text) => model.ancestors(decl.id).any(|a| matches!(a.kind, DeclKind::Class { .. }) && names_match(a, text)),
+      Matcher::Derives(text) => derives_decl(model, decl, text, 0),
+      Matcher::HasParameter(text) => matches!(&decl.kind, DeclKind::Function { parameters, .. }
+        if parameters.iter().any(|p| text.matches(&p.type_name))),
+      Matcher::File(text) => decl.location.file
+        .as_ref()
+        .is_some_and(|file| text.matches(&file.to_string_lossy().replace('\\', "/"))),
+      Matcher::HasAttribute(text) => decl.attributes
+        .iter()
+        .any(|attr| attr.spelling.as_ref().is_some_and(|s| text.matches(s)) || text.matches(&attr.kind)),
+      Matcher::ParameterCount(count) => matches!(&decl.kind, DeclKind::Function { parameters, .. } if parameters.len() == *count),
+      Matcher::Has(matcher) => decl.children
+        .iter()
+        .try_fold(false, |any, c| anyhow::Ok(any || matcher.matches_decl(model, &model.decls[*c])?))?,
+      Matcher::Flag(flag) => flag.matches_decl(decl)?
+    })
+  }
+}
+
+fn unsupported(name: &str) -> anyhow::Error
+{
+  anyhow::anyhow!("{} is unsupported in repl, declarations of the model don't record it", name)
+}
+
+// bases are only known by type names, indirect ones are found through classes of the model with the same qualified name
+fn derives_decl(model: &Model, decl: &Decl, text: &Text, depth: usize) -> bool
+{
+  let DeclKind::Class { bases, .. } = &decl.kind else { return false };
+  if depth > 16 { return false }
+  bases.iter().any(|base| {
+    let name = base.split('<').next().unwrap_or(base).trim();
+    let short = name.rsplit("::").next().unwrap_or(name);
+    text.matches(base) || text.matches(name) || text.matches(short) || model.decls
+      .iter()
+      .filter(|d| matches!(d.kind, DeclKind::Class { .. }) && d.name == short && model.qualified_name(d.id).ends_with(name))
+      .any(|d| derives_decl(model, d, text, depth + 1))
+  })
+}
+
+fn is_record(kind: K) -> bool
+{
+  matches!(kind, K::ClassDecl | K::StructDecl | K::UnionDecl | K::ClassTemplate | K::ClassTemplatePartialSpecialization)
+}
+
+fn ancestors<'tu>(entity: &clang::Entity<'tu>) -> impl Iterator<Item = clang::Entity<'tu>>
+{
+  std::iter::successors(entity.get_semantic_parent(), |parent| parent.get_semantic_parent())
+    .take_while(|parent| parent.get_kind() != K::TranslationUnit)
+}
+
+// short or qualified name matches
+fn names_match(entity: &clang::Entity, text: &Text) -> bool
+{
+  entity.get_name().is_some_and(|name| text.matches(&name) || text.matches(&qualified_name(entity)))
+}
+
+// direct or indirect base class matches by name
+fn derives(entity: &clang::Entity, text: &Text, depth: usize) -> bool
+{
+  if depth > 16 { return false }
+  entity
+    .get_children()
+    .iter()
+    .filter(|c| c.get_kind() == K::BaseSpecifier)
+    .any(|base| {
+      let decl = base.get_type().and_then(|ty| ty.get_declaration());
+      base.get_type().is_some_and(|ty| text.matches(&ty.get_display_name()))
+        || decl.is_some_and(|decl| names_match(&decl, text))
+        || decl.is_some_and(|decl| derives(&decl.get_definition().unwrap_or(decl), text, depth + 1))
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_compile()
+  {
+    let matcher = Matcher::from_query(r#"function(name=~"^get", returns("bool"), in_namespace("floppy"))"#).unwrap();
+    let Matcher::All(all) = matcher else { panic!("expected all_of matcher") };
+    assert!(matches!(all[0], Matcher::Node(NodeKind::Function)));
+    assert!(matches!(&all[1], Matcher::Name(Text::Regex(re)) if re.is_match("get_value") && !re.is_match("value")));
+    assert!(matches!(&all[2], Matcher::Returns(Text::Exact(ty)) if ty == "bool"));
+    assert!(matches!(&all[3], Matcher::InNamespace(Text::Exact(ns)) if ns == "floppy"));
+
+    let matcher = Matcher::from_query("method(is_virtual, !is_override)").unwrap();
+    let Matcher::All(all) = matcher else { panic!("expected all_of matcher") };
+    assert!(matches!(all[1], Matcher::Flag(Flag::Virtual)));
+    assert!(matches!(&all[2], Matcher::Not(m) if matches!(**m, Matcher::Flag(Flag::Override))));
+
+    assert!(Matcher::from_query(r#"class(derives("Base"), has(field(type("int"))))"#).is_ok());
+    assert!(Matcher::from_query("function(parameter_count(2))").is_ok());
+    assert!(Matcher::from_query("functions()").is_err());
+    assert!(Matcher::from_query("method(is_virtual(1))").is_err());
+    assert!(Matcher::from_query(r#"function(returns(1))"#).is_err());
+    assert!(Matcher::from_query(r#"function(name=~"(")"#).is_err());
+    assert!(Matcher::from_query(r#""function""#).is_err());
+  }
+
+  #[test]
+  fn test_matches_decl()
+  {
+    use crate::parser::Parameter;
+    let decl = Decl::fixture;
+    let class = |bases: &[&str]| DeclKind::Class {
+      keyword: "class".to_string(),
+      bases: bases.iter().map(|b| b.to_string()).collect(),
+      template_parameters: vec![]
+    };
+    let function = |return_type: &str, is_virtual: bool| DeclKind::Function {
+      return_type: return_type.to_string(),
+      parameters: vec![Parameter { name: "x".to_string(), type_name: "int".to_string() }],
+      template_parameters: vec![],
+      is_static: false,
+      is_virtual,
+      is_pure_virtual: false,
+      is_const: false,
+      is_variadic: false
+    };
+    let mut model = Model::default();
+    let ns = model.push(None, decl("floppy", "Namespace", DeclKind::Namespace));
+    let base = model.push(Some(ns), decl("Base", "ClassDecl", class(&[])));
+    model.push(Some(base), decl("get_value", "Method", function("bool", true)));
+    let derived = model.push(Some(ns), decl("Derived", "ClassDecl", class(&["floppy::Base"])));
+    model.push(Some(ns), decl("Leaf", "ClassDecl", class(&["Derived"])));
+    model.push(Some(ns), decl("get_flag", "FunctionDecl", function("bool", false)));
+
+    let find = |query: &str| {
+      let matcher = Matcher::from_query(query).unwrap();
+      model.decls.iter().filter(|d| matcher.matches_decl(&model, d).unwrap()).map(|d| d.name.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(find(r#"function(name=~"^get", returns("bool"), in_namespace("floppy"))"#), vec!["get_flag"]);
+    assert_eq!(find(r#"method(is_virtual, !is_override, in_class("floppy::Base"))"#), vec!["get_value"]);
+    assert_eq!(find(r#"class(derives("Base"))"#), vec!["Derived", "Leaf"]);
+    assert_eq!(find(r#"class(has(method(has_parameter("int"))))"#), vec!["Base"]);
+    assert_eq!(find(r#"decl(qualified_name="floppy::Derived")"#), vec!["Derived"]);
+    assert_eq!(model.qualified_name(derived), "floppy::Derived");
+
+    let error = |query: &str| Matcher::from_query(query).unwrap().matches_decl(&model, &model.decls[base]).unwrap_err().to_string();
+    assert!(error("class(is_public)").contains("is_public is unsupported in repl"));
+    assert!(error("decl(has(parameter()))").contains("parameter is unsupported in repl"));
+  }
+}
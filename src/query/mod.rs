@@ -0,0 +1,4 @@
+mod parse;
+mod matcher;
+
+pub use matcher::Matcher;
@@ -0,0 +1,244 @@
+// query syntax:
+//   expr    := unary ('||' unary)*
+//   unary   := '!' unary | primary
+//   primary := ident [('=' | '=~') literal | '(' [expr (',' expr)*] ')'] | literal | '(' expr ')'
+//   literal := string | integer
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op
+{
+  Eq,
+  Regex
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr
+{
+  Call { name: String, args: Vec<Expr> },
+  Field { name: String, op: Op, value: String },
+  Str(String),
+  Int(i64),
+  Not(Box<Expr>),
+  Or(Vec<Expr>)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token
+{
+  Ident(String),
+  Str(String),
+  Int(i64),
+  Punct(&'static str)
+}
+
+pub fn parse(query: &str) -> anyhow::Result<Expr>
+{
+  let tokens = tokenize(query)?;
+  let mut parser = ExprParser { tokens, pos: 0 };
+  let expr = parser.expr()?;
+  match parser.tokens.get(parser.pos) {
+    Some((token, at)) => Err(anyhow::anyhow!("unexpected {:?} at {} in query", token, at)),
+    None => Ok(expr)
+  }
+}
+
+fn tokenize(query: &str) -> anyhow::Result<Vec<(Token, usize)>>
+{
+  let chars = query.char_indices().collect::<Vec<_>>();
+  let mut tokens = vec![];
+  let mut i = 0;
+  while i < chars.len() {
+    let (at, c) = chars[i];
+    match c {
+      _ if c.is_whitespace() => i += 1,
+      '(' | ')' | ',' | '!' => {
+        tokens.push((Token::Punct(match c { '(' => "(", ')' => ")", ',' => ",", _ => "!" }), at));
+        i += 1;
+      },
+      '=' if chars.get(i + 1).is_some_and(|(_, c)| *c == '~') => {
+        tokens.push((Token::Punct("=~"), at));
+        i += 2;
+      },
+      '=' => {
+        tokens.push((Token::Punct("="), at));
+        i += 1;
+      },
+      '|' if chars.get(i + 1).is_some_and(|(_, c)| *c == '|') => {
+        tokens.push((Token::Punct("||"), at));
+        i += 2;
+      },
+      '"' => {
+        let mut value = String::new();
+        i += 1;
+        loop {
+          match chars.get(i) {
+            Some((_, '"')) => break,
+            Some((_, '\\')) => {
+              let (_, escaped) = chars.get(i + 1).ok_or(anyhow::anyhow!("unterminated string at {} in query", at))?;
+              value.push(*escaped);
+              i += 2;
+            },
+            Some((_, c)) => {
+              value.push(*c);
+              i += 1;
+            },
+            None => return Err(anyhow::anyhow!("unterminated string at {} in query", at))
+          }
+        }
+        tokens.push((Token::Str(value), at));
+        i += 1;
+      },
+      _ if c.is_ascii_digit() || c == '-' => {
+        let len = chars[i + 1..].iter().take_while(|(_, c)| c.is_ascii_digit()).count() + 1;
+        let text = chars[i..i + len].iter().map(|(_, c)| c).collect::<String>();
+        let value = text.parse().map_err(|_| anyhow::anyhow!("invalid number {} at {} in query", text, at))?;
+        tokens.push((Token::Int(value), at));
+        i += len;
+      },
+      _ if c.is_alphabetic() || c == '_' => {
+        let len = chars[i..].iter().take_while(|(_, c)| c.is_alphanumeric() || *c == '_').count();
+        tokens.push((Token::Ident(chars[i..i + len].iter().map(|(_, c)| c).collect()), at));
+        i += len;
+      },
+      _ => return Err(anyhow::anyhow!("unexpected character '{}' at {} in query", c, at))
+    }
+  }
+  Ok(tokens)
+}
+
+struct ExprParser
+{
+  tokens: Vec<(Token, usize)>,
+  pos: usize
+}
+
+impl ExprParser
+{
+  fn peek(&self) -> Option<&Token>
+  {
+    self.tokens.get(self.pos).map(|(token, _)| token)
+  }
+
+  fn next(&mut self) -> anyhow::Result<Token>
+  {
+    let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+    self.pos += 1;
+    token.ok_or(anyhow::anyhow!("unexpected end of query"))
+  }
+
+  fn eat(&mut self, punct: &str) -> bool
+  {
+    match self.peek() {
+      Some(Token::Punct(p)) if *p == punct => {
+        self.pos += 1;
+        true
+      },
+      _ => false
+    }
+  }
+
+  fn expect(&mut self, punct: &str) -> anyhow::Result<()>
+  {
+    let at = self.tokens.get(self.pos).map(|(_, at)| *at);
+    match self.eat(punct) {
+      true => Ok(()),
+      false => Err(match at {
+        Some(at) => anyhow::anyhow!("expected '{}' at {} in query", punct, at),
+        None => anyhow::anyhow!("expected '{}' at end of query", punct)
+      })
+    }
+  }
+
+  fn expr(&mut self) -> anyhow::Result<Expr>
+  {
+    let mut alternatives = vec![self.unary()?];
+    while self.eat("||") {
+      alternatives.push(self.unary()?);
+    }
+    Ok(match alternatives.len() {
+      1 => alternatives.pop().unwrap(),
+      _ => Expr::Or(alternatives)
+    })
+  }
+
+  fn unary(&mut self) -> anyhow::Result<Expr>
+  {
+    match self.eat("!") {
+      true => Ok(Expr::Not(Box::new(self.unary()?))),
+      false => self.primary()
+    }
+  }
+
+  fn primary(&mut self) -> anyhow::Result<Expr>
+  {
+    match self.next()? {
+      Token::Ident(name) => {
+        let op = match self.peek() {
+          Some(Token::Punct("=")) => Some(Op::Eq),
+          Some(Token::Punct("=~")) => Some(Op::Regex),
+          _ => None
+        };
+        if let Some(op) = op {
+          self.pos += 1;
+          let value = match self.next()? {
+            Token::Str(value) => value,
+            Token::Int(value) => value.to_string(),
+            token => return Err(anyhow::anyhow!("expected string after {}, found {:?}", name, token))
+          };
+          return Ok(Expr::Field { name, op, value });
+        }
+        let mut args = vec![];
+        if self.eat("(") && !self.eat(")") {
+          loop {
+            args.push(self.expr()?);
+            if self.eat(")") { break }
+            self.expect(",")?;
+          }
+        }
+        Ok(Expr::Call { name, args })
+      },
+      Token::Str(value) => Ok(Expr::Str(value)),
+      Token::Int(value) => Ok(Expr::Int(value)),
+      Token::Punct("(") => {
+        let expr = self.expr()?;
+        self.expect(")")?;
+        Ok(expr)
+      },
+      token => Err(anyhow::anyhow!("unexpected {:?} in query", token))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn call(name: &str, args: Vec<Expr>) -> Expr
+  {
+    Expr::Call { name: name.to_string(), args }
+  }
+
+  #[test]
+  fn test_parse()
+  {
+    assert_eq!(parse(r#"function(name=~"^get", returns("bool"), in_namespace("floppy"))"#).unwrap(), call("function", vec![
+      Expr::Field { name: "name".to_string(), op: Op::Regex, value: "^get".to_string() },
+      call("returns", vec![Expr::Str("bool".to_string())]),
+      call("in_namespace", vec![Expr::Str("floppy".to_string())])
+    ]));
+    assert_eq!(parse(r#"method(is_virtual, !is_override)"#).unwrap(), call("method", vec![
+      call("is_virtual", vec![]),
+      Expr::Not(Box::new(call("is_override", vec![])))
+    ]));
+    assert_eq!(parse(r#"class(derives("Base")) || struct(name = "a\"b")"#).unwrap(), Expr::Or(vec![
+      call("class", vec![call("derives", vec![Expr::Str("Base".to_string())])]),
+      call("struct", vec![Expr::Field { name: "name".to_string(), op: Op::Eq, value: "a\"b".to_string() }])
+    ]));
+    assert_eq!(parse("function(parameter_count(2))").unwrap(), call("function", vec![call("parameter_count", vec![Expr::Int(2)])]));
+    assert!(parse("function(").is_err());
+    assert!(parse("function(name=)").is_err());
+    assert!(parse(r#"function("unterminated)"#).is_err());
+    assert!(parse("function() extra").is_err());
+  }
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeSet;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+// completes command names at the start of the line and entity names everywhere else
+pub struct EntityCompleter
+{
+  names: BTreeSet<String>,
+  commands: Vec<String>
+}
+
+impl EntityCompleter
+{
+  pub fn new(names: BTreeSet<String>, commands: Vec<String>) -> Self
+  {
+    EntityCompleter { names, commands }
+  }
+
+  fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>)
+  {
+    let start = line[..pos]
+      .rfind(|c: char| c.is_whitespace() || matches!(c, '"' | '(' | ',' | '#'))
+      .map(|i| i + 1)
+      .unwrap_or(0);
+    let word = &line[start..pos];
+    let candidates = match start {
+      0 => self.commands.iter().filter(|c| c.starts_with(word)).cloned().collect(),
+      _ => self.names
+        .range(word.to_string()..)
+        .take_while(|name| name.starts_with(word))
+        .take(100)
+        .cloned()
+        .collect()
+    };
+    (start, candidates)
+  }
+}
+
+impl Completer for EntityCompleter
+{
+  type Candidate = String;
+
+  fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)>
+  {
+    Ok(self.candidates(line, pos))
+  }
+}
+
+impl Hinter for EntityCompleter
+{
+  type Hint = String;
+}
+
+impl Highlighter for EntityCompleter {}
+
+impl Validator for EntityCompleter {}
+
+impl Helper for EntityCompleter {}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  #[test]
+  fn test_candidates()
+  {
+    let names = ["floppy", "floppy::foo", "floppy::fmt", "bar"].iter().map(|n| n.to_string()).collect();
+    let completer = EntityCompleter::new(names, vec!["def".to_string(), "dump".to_string(), "find".to_string()]);
+    assert_eq!(completer.candidates("d", 1), (0, vec!["def".to_string(), "dump".to_string()]));
+    assert_eq!(completer.candidates("def floppy::f", 13), (4, vec!["floppy::fmt".to_string(), "floppy::foo".to_string()]));
+    assert_eq!(completer.candidates("query function(in_class(\"ba", 27), (25, vec!["bar".to_string()]));
+  }
+}
@@ -0,0 +1,314 @@
+mod complete;
+
+use std::collections::BTreeSet;
+use colored::Colorize;
+use rustyline::error::ReadlineError;
+use crate::parser::{Decl, DeclKind, Location, Model, Parameter};
+use crate::query::Matcher;
+use complete::EntityCompleter;
+
+// name, argument and description of every command
+const COMMANDS: [(&str, &str, &str); 10] = [
+  ("query", "<expr>", "declarations matching a query, e.g. query method(is_virtual)"),
+  ("find", "<regex>", "declarations with qualified names matching a regex"),
+  ("dump", "<entity>", "declaration and its children as json"),
+  ("def", "<entity>", "definition of the declaration with a source snippet"),
+  ("children", "[entity]", "children of the declaration, or top level declarations"),
+  ("type", "<entity>", "type or signature of the declaration"),
+  ("history", "", "previously entered commands"),
+  ("help", "", "this message"),
+  ("quit", "", "exit"),
+  ("exit", "", "exit")
+];
+
+// interactive queries and navigation over a parsed model. entities are referenced by qualified name, name or `#id`
+pub struct Repl<'m>
+{
+  model: &'m Model
+}
+
+impl<'m> Repl<'m>
+{
+  pub fn new(model: &'m Model) -> Self
+  {
+    Repl { model }
+  }
+
+  pub fn run(&self) -> anyhow::Result<()>
+  {
+    let names = self.model.decls
+      .iter()
+      .flat_map(|decl| [decl.name.clone(), self.model.qualified_name(decl.id)])
+      .filter(|name| !name.is_empty())
+      .collect::<BTreeSet<_>>();
+    let mut editor = rustyline::Editor::<EntityCompleter, rustyline::history::DefaultHistory>::new()?;
+    editor.set_helper(Some(EntityCompleter::new(names, COMMANDS.iter().map(|(name, _, _)| name.to_string()).collect())));
+    println!("  ☑️ {} declarations loaded. type {} for the list of commands", self.model.decls.len().to_string().bold().green(), "help".bold());
+    let mut history = vec![];
+    loop {
+      let line = match editor.readline("cxt> ") {
+        Ok(line) => line,
+        Err(ReadlineError::Interrupted) => continue,
+        Err(ReadlineError::Eof) => break,
+        Err(e) => return Err(e.into())
+      };
+      let line = line.trim();
+      if line.is_empty() { continue }
+      editor.add_history_entry(line)?;
+      history.push(line.to_string());
+      match self.execute(line, &history) {
+        Ok(Some(output)) => println!("{}", output),
+        Ok(None) => break,
+        Err(e) => println!("{} {}", "error:".bold().red(), e)
+      }
+    }
+    Ok(())
+  }
+
+  // output of a single command, none to quit
+  pub fn execute(&self, line: &str, history: &[String]) -> anyhow::Result<Option<String>>
+  {
+    let (command, arg) = line.split_once(char::is_whitespace).map(|(c, a)| (c, a.trim())).unwrap_or((line, ""));
+    let lines = match command {
+      "query" | "q" => {
+        let matcher = Matcher::from_query(arg)?;
+        let mut found = vec![];
+        for decl in &self.model.decls {
+          if matcher.matches_decl(self.model, decl)? {
+            found.push(decl);
+          }
+        }
+        self.list(found.into_iter())
+      },
+      "find" | "f" => {
+        let re = regex::Regex::new(arg)?;
+        self.list(self.model.decls.iter().filter(|decl| re.is_match(&self.model.qualified_name(decl.id))))
+      },
+      "dump" => vec![serde_json::to_string_pretty(&self.subtree(self.resolve(arg)?))?],
+      "def" | "d" => {
+        let decl = &self.model.decls[self.resolve(arg)?];
+        // a forward declaration may come from a unit that never saw the definition, another record of it may have
+        let definition = decl.definition.as_ref().or_else(|| self.model.decls
+          .iter()
+          .filter(|other| decl.usr.is_some() && other.usr == decl.usr)
+          .find_map(|other| other.definition.as_ref())
+        );
+        let location = definition.unwrap_or(&decl.location);
+        let mut lines = vec![format!("{} {}", format_location(location).bold().magenta(), match definition {
+          Some(_) => "definition".to_string(),
+          None => "declaration, definition not found".to_string().yellow().to_string()
+        })];
+        lines.extend(snippet(location, 2));
+        lines
+      },
+      "children" | "ls" => match arg {
+        "" => self.list(self.model.decls.iter().filter(|decl| decl.parent.is_none())),
+        _ => self.list(self.model.decls[self.resolve(arg)?].children.iter().map(|id| &self.model.decls[*id]))
+      },
+      "type" | "t" => vec![self.signature(&self.model.decls[self.resolve(arg)?])],
+      "history" => history.iter().enumerate().map(|(i, line)| format!("{:>4}  {}", i + 1, line)).collect(),
+      "help" => COMMANDS
+        .iter()
+        .map(|(name, argument, help)| format!("  {} {:<10} {}", format!("{:<10}", name).bold(), argument, help))
+        .collect(),
+      "quit" | "exit" => return Ok(None),
+      _ => return Err(anyhow::anyhow!("unknown command: {}. type help for the list of commands", command))
+    };
+    Ok(Some(lines.join("\n")))
+  }
+
+  // declaration by `#id`, qualified name or name. ambiguous references list all candidates
+  fn resolve(&self, reference: &str) -> anyhow::Result<usize>
+  {
+    anyhow::ensure!(!reference.is_empty(), "expected entity name or #id");
+    if let Some(id) = reference.strip_prefix('#') {
+      let id = id.parse::<usize>()?;
+      anyhow::ensure!(id < self.model.decls.len(), "no declaration #{}", id);
+      return Ok(id);
+    }
+    let mut candidates = self.model.decls
+      .iter()
+      .filter(|decl| self.model.qualified_name(decl.id) == reference)
+      .collect::<Vec<_>>();
+    if candidates.is_empty() {
+      candidates = self.model.decls.iter().filter(|decl| decl.name == reference).collect();
+    }
+    match candidates.as_slice() {
+      [] => Err(anyhow::anyhow!("no declaration named {}", reference)),
+      [decl] => Ok(decl.id),
+      _ => Err(anyhow::anyhow!("{} is ambiguous, use one of:\n{}", reference, self.list(candidates.into_iter()).join("\n")))
+    }
+  }
+
+  fn list<'d>(&self, decls: impl Iterator<Item = &'d Decl>) -> Vec<String>
+  {
+    let lines = decls
+      .map(|decl| format!("{:>6} [{}] {} {}{}",
+        format!("#{}", decl.id).dimmed(),
+        decl.entity_kind.bold(),
+        decl.signature.as_ref().unwrap_or(&self.model.qualified_name(decl.id)).bold().green(),
+        format_location(&decl.location).magenta(),
+        decl.usr.as_ref().map(|usr| format!(" {}", usr.dimmed())).unwrap_or_default()
+      ))
+      .collect::<Vec<_>>();
+    match lines.is_empty() {
+      true => vec!["no declarations found".to_string().yellow().to_string()],
+      false => lines
+    }
+  }
+
+  // declaration with nested children instead of their ids
+  fn subtree(&self, id: usize) -> serde_json::Value
+  {
+    let decl = &self.model.decls[id];
+    let mut value = serde_json::to_value(decl).unwrap_or_default();
+    value["qualified_name"] = self.model.qualified_name(id).into();
+    value["children"] = decl.children.iter().map(|child| self.subtree(*child)).collect();
+    value
+  }
+
+  fn signature(&self, decl: &Decl) -> String
+  {
+    if let Some(signature) = &decl.signature {
+      return signature.clone();
+    }
+    let name = self.model.qualified_name(decl.id);
+    match &decl.kind {
+      DeclKind::Namespace => format!("namespace {}", name),
+      DeclKind::Class { keyword, bases, .. } => match bases.is_empty() {
+        true => format!("{} {}", keyword, name),
+        false => format!("{} {} : {}", keyword, name, bases.join(", "))
+      },
+      DeclKind::Function { return_type, parameters, is_static, is_virtual, is_pure_virtual, is_const, is_variadic, .. } => format!("{}{}{}({}{}){}{}",
+        if *is_static { "static " } else if *is_virtual { "virtual " } else { "" },
+        match return_type.as_str() {
+          "" => String::new(),
+          ty => format!("{} ", ty)
+        },
+        name,
+        parameters.iter().map(format_parameter).collect::<Vec<_>>().join(", "),
+        if *is_variadic { ", ..." } else { "" },
+        if *is_const { " const" } else { "" },
+        if *is_pure_virtual { " = 0" } else { "" }
+      ),
+      DeclKind::Enum { underlying_type, is_scoped } => format!("enum {}{}{}",
+        if *is_scoped { "class " } else { "" },
+        name,
+        underlying_type.as_ref().map(|ty| format!(" : {}", ty)).unwrap_or_default()
+      ),
+      DeclKind::Enumerator { value } => format!("{}{}", name, value.map(|v| format!(" = {}", v)).unwrap_or_default()),
+      DeclKind::Typedef { underlying_type } => format!("using {} = {}", name, underlying_type.as_deref().unwrap_or("?")),
+      DeclKind::Variable { type_name, is_static } => format!("{}{} {}", if *is_static { "static " } else { "" }, type_name, name)
+    }
+  }
+}
+
+fn format_parameter(parameter: &Parameter) -> String
+{
+  match parameter.name.as_str() {
+    "" => parameter.type_name.clone(),
+    name => format!("{} {}", parameter.type_name, name)
+  }
+}
+
+fn format_location(location: &Location) -> String
+{
+  format!("{}:{}:{}",
+    location.file.as_ref().map(|f| f.display().to_string()).unwrap_or("unknown".to_string()),
+    location.line,
+    location.column
+  )
+}
+
+// lines around location with line numbers, the located one is marked
+fn snippet(location: &Location, context: u32) -> Vec<String>
+{
+  let Some(contents) = location.file.as_ref().and_then(|file| std::fs::read_to_string(file).ok()) else { return vec![] };
+  let first = location.line.saturating_sub(context).max(1);
+  contents
+    .lines()
+    .enumerate()
+    .map(|(i, line)| (i as u32 + 1, line))
+    .skip_while(|(n, _)| *n < first)
+    .take_while(|(n, _)| *n <= location.line + context)
+    .map(|(n, line)| match n == location.line {
+      true => format!("{} {:>5} | {}", ">".bold().green(), n, line),
+      false => format!("  {:>5} | {}", n.to_string().dimmed(), line)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+  use super::*;
+
+  fn model() -> Model
+  {
+    let decl = |name: &str, entity_kind: &str, kind: DeclKind| Decl {
+      location: Location { file: Some("floppy.h".into()), line: 3, column: 1 },
+      ..Decl::fixture(name, entity_kind, kind)
+    };
+    let function = |is_const: bool| DeclKind::Function {
+      return_type: "bool".to_string(),
+      parameters: vec![Parameter { name: "x".to_string(), type_name: "int".to_string() }],
+      template_parameters: vec![],
+      is_static: false,
+      is_virtual: false,
+      is_pure_virtual: false,
+      is_const,
+      is_variadic: false
+    };
+    let mut model = Model::default();
+    let ns = model.push(None, decl("floppy", "Namespace", DeclKind::Namespace));
+    model.push(Some(ns), decl("foo", "FunctionDecl", function(false)));
+    let class = model.push(Some(ns), decl("Bar", "ClassDecl", DeclKind::Class { keyword: "struct".to_string(), bases: vec![], template_parameters: vec![] }));
+    model.push(Some(class), decl("foo", "Method", function(true)));
+    model
+  }
+
+  #[test]
+  fn test_execute()
+  {
+    let model = model();
+    let repl = Repl::new(&model);
+    let run = |line: &str| repl.execute(line, &["help".to_string()]).unwrap().unwrap();
+    assert_eq!(repl.resolve("#2").unwrap(), 2);
+    assert_eq!(repl.resolve("floppy::Bar::foo").unwrap(), 3);
+    assert_eq!(repl.resolve("Bar").unwrap(), 2);
+    assert!(repl.resolve("foo").is_err());
+    assert!(repl.resolve("baz").is_err());
+    assert!(run("query method(is_const)").contains("floppy::Bar::foo"));
+    assert!(run("children floppy").contains("floppy::Bar"));
+    assert!(run("type floppy::Bar::foo").contains("bool floppy::Bar::foo(int x) const"));
+    assert!(run("type #2").contains("struct floppy::Bar"));
+    assert!(run("history").contains("help"));
+    assert!(run("help").contains("<expr>     declarations matching a query"));
+    assert!(repl.execute("query function(is_inline)", &[]).is_err());
+    let dump = serde_json::from_str::<serde_json::Value>(&run("dump floppy::Bar")).unwrap();
+    assert_eq!(dump["children"][0]["qualified_name"], "floppy::Bar::foo");
+    assert!(repl.execute("quit", &[]).unwrap().is_none());
+    assert!(repl.execute("frobnicate", &[]).is_err());
+  }
+
+  #[test]
+  fn test_def()
+  {
+    let at = |line: u32| Location { file: Some("floppy.h".into()), line, column: 1 };
+    let class = |usr: &str, definition: Option<Location>| Decl {
+      location: at(3),
+      usr: Some(usr.to_string()),
+      definition,
+      ..Decl::fixture("Bar", "ClassDecl", DeclKind::Class { keyword: "struct".to_string(), bases: vec![], template_parameters: vec![] })
+    };
+    let mut model = Model::default();
+    model.push(None, class("c:@S@Bar", None));
+    model.push(None, class("c:@S@Bar", Some(at(10))));
+    model.push(None, Decl { name: "Baz".to_string(), qualified_name: "Baz".to_string(), ..class("c:@S@Baz", None) });
+    let repl = Repl::new(&model);
+    let run = |line: &str| repl.execute(line, &[]).unwrap().unwrap();
+    let def = run("def #0");
+    assert!(def.contains("floppy.h:10:1") && !def.contains("not found"));
+    assert!(run("def Baz").contains("definition not found"));
+  }
+}